@@ -1,16 +1,29 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io::BufWriter;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context as _, Result};
-use kenny::puzzle::Puzzle;
+use kenny::puzzle::{CollectionWriter, Puzzle, Solution};
+use kenny::solve::{DifficultyTier, SolveResult};
 
 use crate::options::Options;
 use crate::puzzle_folder_builder::PuzzleFolderBuilder;
+use crate::report::SolveReport;
 
 pub(crate) struct Context {
     options: Options,
     puzzle_path_iter: Option<PuzzlePathIter>,
+    booklet_puzzles: Vec<BookletPuzzle>,
+    collection_writer: Option<CollectionWriter<BufWriter<File>>>,
+    report: SolveReport,
+}
+
+/// A puzzle collected to be saved in a booklet after every puzzle is sourced
+pub(crate) struct BookletPuzzle {
+    pub puzzle: Puzzle,
+    pub solution: Option<Solution>,
+    pub difficulty: Option<DifficultyTier>,
 }
 
 impl Context {
@@ -22,7 +35,7 @@ impl Context {
                         bail!("Path does not exist: {}", parent.display());
                     }
                 }
-                fs::create_dir(&path)
+                fs::create_dir(path)
                     .with_context(|| format!("Error creating output path: {}", path.display()))?;
             }
         }
@@ -32,9 +45,21 @@ impl Context {
             n: 1,
         });
 
+        let collection_writer = options
+            .save_collection()
+            .map(|path| {
+                let format = options.output_format().collection_format();
+                CollectionWriter::create(path, format)
+                    .with_context(|| format!("Error creating collection: {}", path.display()))
+            })
+            .transpose()?;
+
         Ok(Self {
             options,
             puzzle_path_iter,
+            booklet_puzzles: Vec::new(),
+            collection_writer,
+            report: SolveReport::default(),
         })
     }
 
@@ -42,6 +67,46 @@ impl Context {
         &self.options
     }
 
+    pub fn booklet_puzzles(&self) -> &[BookletPuzzle] {
+        &self.booklet_puzzles
+    }
+
+    pub fn add_booklet_puzzle(&mut self, puzzle: BookletPuzzle) {
+        self.booklet_puzzles.push(puzzle);
+    }
+
+    pub fn report(&self) -> &SolveReport {
+        &self.report
+    }
+
+    pub fn add_to_report(&mut self, index: usize, puzzle: &Puzzle, result: &SolveResult) {
+        self.report.add(index, puzzle, result);
+    }
+
+    pub fn add_invalid_to_report(&mut self, index: usize, reason: String) {
+        self.report.add_invalid(index, reason);
+    }
+
+    pub fn write_to_collection(&mut self, puzzle: &Puzzle) -> Result<()> {
+        if let Some(writer) = &mut self.collection_writer {
+            writer
+                .write(puzzle)
+                .context("Error saving puzzle to collection")?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the collection file, returning the number of puzzles saved to it
+    pub fn finish_collection(&mut self) -> Result<Option<usize>> {
+        let writer = match self.collection_writer.take() {
+            None => return Ok(None),
+            Some(writer) => writer,
+        };
+        let count = writer.count();
+        writer.finish().context("Error saving collection")?;
+        Ok(Some(count))
+    }
+
     pub fn next_puzzle_path(&mut self) -> PathBuf {
         let iter = self.puzzle_path_iter.as_mut().expect("no puzzle path");
         iter.next().unwrap()
@@ -51,25 +116,32 @@ impl Context {
 pub(crate) struct PuzzleContext<'a> {
     context: &'a mut Context,
     puzzle: &'a Puzzle,
+    /// The position of the puzzle among the sourced puzzles, counting from 1
+    index: usize,
     folder_builder: Option<PuzzleFolderBuilder>,
 }
 
 impl<'a> PuzzleContext<'a> {
-    pub fn new(context: &'a mut Context, puzzle: &'a Puzzle) -> Result<Self> {
+    pub fn new(context: &'a mut Context, puzzle: &'a Puzzle, index: usize) -> Result<Self> {
         let folder_builder = if context.options().save_any() {
-            Some(PuzzleFolderBuilder::new()?)
+            Some(PuzzleFolderBuilder::new(context.options().image_format())?)
         } else {
             None
         };
         Ok(Self {
             context,
             puzzle,
+            index,
             folder_builder,
         })
     }
 
-    pub fn puzzle(&self) -> &Puzzle {
-        &self.puzzle
+    pub fn puzzle(&self) -> &'a Puzzle {
+        self.puzzle
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
     }
 
     pub fn folder_builder(&self) -> Option<&PuzzleFolderBuilder> {
@@ -3,22 +3,53 @@
 #![warn(trivial_numeric_casts)]
 #![warn(unused_qualifications)]
 
+use std::error::Error as _;
 use std::fs;
 use std::panic::{catch_unwind, resume_unwind};
+use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{bail, Context as _, Result};
 use itertools::Itertools;
-use kenny::collections::square::SquareValue;
-use kenny::image::PuzzleImageBuilder;
-use kenny::puzzle::{Puzzle, Solution};
-use kenny::solve::{PuzzleSolver, SolveResult};
+use kenny::error::ReadCollectionError;
+use kenny::generate::{PuzzleGenerator, MAX_DIFFICULTY_ATTEMPTS};
+use kenny::image::{BookletBuilder, PuzzleImageBuilder};
+use kenny::puzzle::{CollectionReader, PlayerGrid, Puzzle, Solution};
+use kenny::solve::{MultipleSolutionsData, PuzzleSolver, SolveResult};
+use kenny::text::PuzzleTextBuilder;
+#[cfg(feature = "json")]
+use serde::Serialize;
 
-use crate::context::{Context, PuzzleContext};
-use crate::options::Options;
+use crate::context::{BookletPuzzle, Context, PuzzleContext};
+use crate::options::{DataFormat, Format, Options};
+
+/// Prints a message like `print!`. With JSON output, messages are printed to stderr so that
+/// stdout only has JSON.
+macro_rules! message {
+    ($options:expr, $($arg:tt)*) => {
+        if $options.output_format() != DataFormat::Text {
+            eprint!($($arg)*)
+        } else {
+            print!($($arg)*)
+        }
+    };
+}
+
+/// Prints a message like `println!`, to stderr with JSON output
+macro_rules! messageln {
+    ($options:expr, $($arg:tt)*) => {
+        if $options.output_format() != DataFormat::Text {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
 
 mod context;
 mod options;
+mod play;
 mod puzzle_folder_builder;
+mod report;
 
 fn main() -> Result<()> {
     env_logger::init();
@@ -34,27 +65,133 @@ impl Context {
             options::Source::File(_) => {
                 self.start_file()?;
             }
-            &options::Source::Generate(options::Generate { count, width, .. }) => {
-                self.start_generate(count, width)?;
+            options::Source::Collection(path) => {
+                let path = path.clone();
+                self.start_collection(&path)?;
+            }
+            options::Source::Generate(generate) => {
+                let generate = generate.clone();
+                self.start_generate(&generate)?;
             }
         }
+        self.save_booklet()?;
+        if let Some(count) = self.finish_collection()? {
+            let path = self.options().save_collection().unwrap();
+            messageln!(
+                self.options(),
+                "Saved {} puzzle{} to {}",
+                count,
+                if count == 1 { "" } else { "s" },
+                path.display()
+            );
+        }
+        self.save_report()?;
+        Ok(())
+    }
+
+    /// Prints a summary of the solve results of a collection, and saves it with `--report`
+    fn save_report(&self) -> Result<()> {
+        if self.report().is_empty() {
+            return Ok(());
+        }
+        if let options::Source::Collection(_) = self.options().source() {
+            message!(self.options(), "\n{}", self.report());
+        }
+        if let Some(path) = self.options().report() {
+            fs::write(path, self.report().to_string())
+                .with_context(|| format!("Error saving report to {}", path.display()))?;
+            messageln!(self.options(), "Saved report to {}", path.display());
+        }
+        Ok(())
+    }
+
+    fn save_booklet(&self) -> Result<()> {
+        let options = match self.options().booklet() {
+            None => return Ok(()),
+            Some(options) => options,
+        };
+        let mut builder = BookletBuilder::new(options.title.as_str());
+        builder
+            .layout(options.columns, options.rows)
+            .page_size(options.page_size)
+            .theme(self.options().theme());
+        for p in self.booklet_puzzles() {
+            builder.puzzle(&p.puzzle, p.solution.as_ref(), p.difficulty);
+        }
+        builder
+            .save_pdf(&options.path)
+            .with_context(|| format!("Error saving booklet to {}", options.path.display()))?;
+        messageln!(
+            self.options(),
+            "Saved booklet to {}",
+            options.path.display()
+        );
         Ok(())
     }
 
     fn start_file(&mut self) -> Result<()> {
         let path = self.options().source().file().unwrap();
-        println!("Reading puzzle from \"{}\"", path.display());
-        let puzzle = Puzzle::from_file(path)?;
-        let mut context = PuzzleContext::new(self, &puzzle)?;
+        messageln!(self.options(), "Reading puzzle from \"{}\"", path.display());
+        let puzzle = match self.options().input_format() {
+            DataFormat::Text => Puzzle::from_file(path)?,
+            #[cfg(feature = "json")]
+            DataFormat::Json => Puzzle::from_json_file(path)?,
+        };
+        let mut context = PuzzleContext::new(self, &puzzle, 1)?;
         context.on_puzzle_sourced()?;
         Ok(())
     }
 
-    fn start_generate(&mut self, count: u32, width: SquareValue) -> Result<()> {
+    fn start_collection(&mut self, path: &Path) -> Result<()> {
+        messageln!(
+            self.options(),
+            "Reading puzzles from \"{}\"",
+            path.display()
+        );
+        let format = self.options().input_format().collection_format();
+        let reader = CollectionReader::open(path, format)
+            .with_context(|| format!("Error reading collection from {}", path.display()))?;
+        for (i, puzzle) in reader.enumerate() {
+            let index = i + 1;
+            messageln!(self.options(), "Puzzle {}", index);
+            let puzzle = match puzzle {
+                Ok(puzzle) => puzzle,
+                Err(ReadCollectionError::Io(e)) => {
+                    return Err(e).with_context(|| {
+                        format!("Error reading collection from {}", path.display())
+                    });
+                }
+                Err(e) => {
+                    // the source has the reason without the puzzle index
+                    let reason = e.source().map_or_else(|| e.to_string(), |s| s.to_string());
+                    messageln!(self.options(), "Invalid puzzle: {}", reason);
+                    if self.options().solve().is_some() {
+                        self.add_invalid_to_report(index, reason);
+                    }
+                    continue;
+                }
+            };
+            let mut context = PuzzleContext::new(self, &puzzle, index)?;
+            context.on_puzzle_sourced()?;
+        }
+        Ok(())
+    }
+
+    fn start_generate(&mut self, generate: &options::Generate) -> Result<()> {
+        let options::Generate {
+            count,
+            width,
+            include_unsolvable,
+            difficulty,
+            ref config,
+            ..
+        } = *generate;
+        let mut generator = PuzzleGenerator::new(config.clone());
         let mut included_count = 0;
         let mut attempt = 1;
         while included_count < count {
-            println!(
+            messageln!(
+                self.options(),
                 "Generating puzzle {}/{}{attempt}",
                 included_count + 1,
                 count,
@@ -64,14 +201,32 @@ impl Context {
                     format!(" (attempt {})", attempt)
                 }
             );
-            let puzzle = Puzzle::generate_untested(width);
-            let mut context = PuzzleContext::new(self, &puzzle)?;
+            let mut puzzle = match difficulty {
+                None if include_unsolvable => generator.generate_untested(width),
+                None => generator.generate(width),
+                Some(tier) => match generator.generate_with_difficulty(width, tier) {
+                    Some(puzzle) => puzzle,
+                    None => bail!(
+                        "Could not generate a puzzle with difficulty {} and width {} after {} attempts",
+                        tier,
+                        width,
+                        MAX_DIFFICULTY_ATTEMPTS
+                    ),
+                },
+            };
+            let seed = generator.last_seed().unwrap();
+            messageln!(self.options(), "Seed: {}", seed);
+            puzzle.metadata_mut().insert("seed", &seed.to_string());
+            let mut context = PuzzleContext::new(self, &puzzle, included_count as usize + 1)?;
+            if let Some(folder_builder) = context.folder_builder() {
+                folder_builder.write_seed(seed)?;
+            }
             let included = context.on_puzzle_sourced()?;
             if included {
                 included_count += 1;
                 attempt = 1;
             } else {
-                println!("Puzzle discarded");
+                messageln!(self.options(), "Puzzle discarded");
                 attempt += 1;
             }
         }
@@ -81,8 +236,19 @@ impl Context {
 
 impl PuzzleContext<'_> {
     fn on_puzzle_sourced(&mut self) -> Result<bool> {
-        print_puzzle(self.puzzle());
+        if self.options().output_format() == DataFormat::Text {
+            print_puzzle(self.puzzle(), self.options().format());
+        }
         self.save_puzzle()?;
+        if let Some(count_options) = self.options().count_solutions() {
+            self.on_count_solutions(count_options);
+        }
+        if let Some(check_options) = self.options().check() {
+            self.on_check_grid(check_options)?;
+        }
+        if self.options().play() {
+            play::play(self.puzzle())?;
+        }
         let unwind_result = self.options().solve().map(|solve_options| {
             // catch a panic to save puzzle output
             catch_unwind(|| self.on_solve_puzzle(solve_options))
@@ -96,21 +262,63 @@ impl PuzzleContext<'_> {
         } else {
             None
         };
-        if let Some(unwind_result) = unwind_result {
-            match unwind_result {
-                Err(e) => resume_unwind(e),
-                Ok(solve_result) => {
-                    solve_result?;
-                }
-            }
-        }
+        let solve_result = match unwind_result {
+            None => None,
+            Some(Err(e)) => resume_unwind(e),
+            Some(Ok(solve_result)) => Some(solve_result?),
+        };
         if let Some(result) = save_result {
             // propagate save error after checking for other errors
             result?;
         }
+        if save_folder {
+            if let Some(result) = &solve_result {
+                let (index, puzzle) = (self.index(), self.puzzle());
+                self.add_to_report(index, puzzle, result);
+            }
+            let puzzle = self.puzzle();
+            self.write_to_collection(puzzle)?;
+        }
+        #[cfg(feature = "json")]
+        if save_folder && self.options().output_format() == DataFormat::Json {
+            self.print_json(solve_result.as_ref());
+        }
+        if save_folder && self.options().booklet().is_some() {
+            self.add_to_booklet(solve_result)?;
+        }
         Ok(save_folder)
     }
 
+    /// Prints the puzzle and its solve result as one line of JSON
+    #[cfg(feature = "json")]
+    fn print_json(&self, solve_result: Option<&SolveResult>) {
+        let json = PuzzleOutput {
+            puzzle: self.puzzle(),
+            solve_result,
+        };
+        println!("{}", serde_json::to_string(&json).unwrap());
+    }
+
+    /// Adds the puzzle to the booklet with its difficulty and its solution in the answer pages.
+    /// The puzzle is solved here unless it was already solved.
+    fn add_to_booklet(&mut self, solve_result: Option<SolveResult>) -> Result<()> {
+        let solve_result = match solve_result {
+            Some(result) => result,
+            None => PuzzleSolver::new(self.puzzle()).solve()?,
+        };
+        let (solution, difficulty) = match solve_result {
+            SolveResult::Solved(data) => (Some(data.solution), Some(data.difficulty.tier())),
+            _ => (None, None),
+        };
+        let puzzle = self.puzzle().clone();
+        self.add_booklet_puzzle(BookletPuzzle {
+            puzzle,
+            solution,
+            difficulty,
+        });
+        Ok(())
+    }
+
     fn should_include(&self, result: &SolveResult) -> bool {
         let context = match self.options().source() {
             options::Source::Generate(context) => context,
@@ -120,6 +328,9 @@ impl PuzzleContext<'_> {
             context.include_solvable
                 && (!context.require_search || solve.used_search)
                 && (!context.no_require_search || !solve.used_search)
+                && context
+                    .difficulty
+                    .is_none_or(|tier| tier == solve.difficulty.tier())
         } else {
             context.include_unsolvable
         }
@@ -127,7 +338,9 @@ impl PuzzleContext<'_> {
 
     fn save_puzzle(&self) -> Result<()> {
         if self.options().save_puzzle() {
-            self.folder_builder().unwrap().write_puzzle(self.puzzle())?;
+            self.folder_builder()
+                .unwrap()
+                .write_puzzle(self.puzzle(), self.options().output_format())?;
         }
         if self.options().save_image() {
             self.save_image()?;
@@ -136,31 +349,165 @@ impl PuzzleContext<'_> {
     }
 
     fn save_image(&self) -> Result<()> {
-        let image = PuzzleImageBuilder::new(self.puzzle()).build();
+        let image = self.image_builder().build();
         self.folder_builder().unwrap().write_puzzle_image(&image)?;
         Ok(())
     }
 
+    fn on_count_solutions(&self, count_options: &options::CountSolutions) {
+        let solver = PuzzleSolver::new(self.puzzle());
+        let solutions = solver.solutions();
+        let solutions: Vec<_> = match count_options.limit {
+            None => solutions.collect(),
+            Some(limit) => solutions.take(limit).collect(),
+        };
+        for (i, solution) in solutions.iter().enumerate() {
+            messageln!(
+                self.options(),
+                "Solution {}:\n{}",
+                i + 1,
+                self.solution_text(solution)
+            );
+        }
+        let limit_reached = count_options.limit == Some(solutions.len());
+        messageln!(
+            self.options(),
+            "Found {}{} solution{}",
+            if limit_reached { "at least " } else { "" },
+            solutions.len(),
+            if solutions.len() == 1 { "" } else { "s" },
+        );
+    }
+
+    fn on_check_grid(&self, check_options: &options::Check) -> Result<()> {
+        let path = &check_options.path;
+        messageln!(self.options(), "Checking grid from \"{}\"", path.display());
+        let str = fs::read_to_string(path)
+            .with_context(|| format!("Error reading grid from {}", path.display()))?;
+        let grid = PlayerGrid::parse(&str)?;
+        let puzzle = self.puzzle();
+        let conflicts = if check_options.with_solution {
+            match PuzzleSolver::new(puzzle).solve()? {
+                SolveResult::Solved(ref data) => {
+                    puzzle.check_grid_with_solution(&grid, &data.solution)?
+                }
+                _ => {
+                    messageln!(
+                        self.options(),
+                        "The puzzle does not have a unique solution to check entries against"
+                    );
+                    puzzle.check_grid(&grid)?
+                }
+            }
+        } else {
+            puzzle.check_grid(&grid)?
+        };
+        if self.options().format() == Format::Text {
+            message!(
+                self.options(),
+                "{}",
+                PuzzleTextBuilder::new(puzzle).entries(&grid).build()
+            );
+        }
+        if conflicts.is_empty() {
+            messageln!(self.options(), "No conflicts found");
+        } else {
+            messageln!(
+                self.options(),
+                "Found {} conflict{}:",
+                conflicts.len(),
+                if conflicts.len() == 1 { "" } else { "s" }
+            );
+            for conflict in &conflicts {
+                messageln!(self.options(), "  {}", conflict.describe(puzzle));
+            }
+        }
+        if self.options().save_image() {
+            let mut builder = self.image_builder();
+            builder.entries(&grid).conflicts(&conflicts);
+            let image = builder.build();
+            self.folder_builder()
+                .unwrap()
+                .write_checked_grid_image(&image)?;
+        }
+        Ok(())
+    }
+
+    fn solution_text(&self, solution: &Solution) -> String {
+        match self.options().format() {
+            Format::Cages => solution.to_string(),
+            Format::Text => PuzzleTextBuilder::new(self.puzzle())
+                .solution(solution)
+                .build(),
+        }
+    }
+
     fn on_solve_puzzle(&self, solve_options: &options::Solve) -> Result<SolveResult> {
         let solver = self.build_solver(solve_options)?;
         let result = solver.solve()?;
-        let msg = match result {
-            SolveResult::Unsolvable => "Puzzle is not solvable",
-            SolveResult::Solved(_) => "Puzzle solved",
-            SolveResult::MultipleSolutions => "Puzzle has multiple solutions",
-        };
-        println!("{}", msg);
+        match result {
+            SolveResult::Unsolvable => messageln!(self.options(), "Puzzle is not solvable"),
+            SolveResult::Solved(ref data) => {
+                messageln!(self.options(), "Puzzle solved");
+                if self.options().format() == Format::Text {
+                    message!(self.options(), "{}", self.solution_text(&data.solution));
+                }
+                messageln!(self.options(), "Difficulty: {}", data.difficulty);
+            }
+            SolveResult::MultipleSolutions(ref data) => {
+                messageln!(self.options(), "Puzzle has multiple solutions");
+                let width = self.puzzle().width() as usize;
+                // named like "R1C2", as in explanations and hints
+                let cells = data
+                    .differing_cells
+                    .iter()
+                    .map(|&cell| format!("R{}C{}", cell / width + 1, cell % width + 1))
+                    .join(" ");
+                messageln!(
+                    self.options(),
+                    "Cells that differ between solutions: {}",
+                    cells
+                );
+            }
+        }
         if self.should_include(&result) {
-            if let Some(result) = result.solved() {
-                self.save_solved_image(solve_options, &result.solution)?;
+            match result {
+                SolveResult::Solved(ref data) => {
+                    self.save_solved_image(solve_options, &data.solution)?
+                }
+                SolveResult::MultipleSolutions(ref data) => {
+                    self.save_multiple_solutions_images(solve_options, data)?
+                }
+                SolveResult::Unsolvable => {}
             }
         }
         Ok(result)
     }
 
+    /// Saves an image of each of two solutions with the cells that differ highlighted
+    fn save_multiple_solutions_images(
+        &self,
+        solve_options: &options::Solve,
+        data: &MultipleSolutionsData,
+    ) -> Result<()> {
+        if solve_options.save_image {
+            for (i, solution) in data.solutions.iter().enumerate() {
+                let mut builder = self.image_builder();
+                builder
+                    .solution(solution)
+                    .highlighted_cells(&data.differing_cells);
+                let image = builder.build();
+                self.folder_builder()
+                    .unwrap()
+                    .write_multiple_solutions_image(i + 1, &image)?;
+            }
+        }
+        Ok(())
+    }
+
     fn save_solved_image(&self, solve_options: &options::Solve, solution: &Solution) -> Result<()> {
         if solve_options.save_image {
-            let mut builder = PuzzleImageBuilder::new(self.puzzle());
+            let mut builder = self.image_builder();
             builder.solution(solution);
             let image = builder.build();
             self.folder_builder()
@@ -170,12 +517,32 @@ impl PuzzleContext<'_> {
         Ok(())
     }
 
+    /// Starts an image of the puzzle with the theme from the options
+    fn image_builder(&self) -> PuzzleImageBuilder<'_> {
+        let mut builder = PuzzleImageBuilder::new(self.puzzle());
+        builder.theme(self.options().theme());
+        builder
+    }
+
     fn build_solver(&self, solve_options: &options::Solve) -> Result<PuzzleSolver<'_>> {
         let mut solver = PuzzleSolver::new(self.puzzle());
+        solver.step_image_theme(self.options().theme());
         if solve_options.save_step_images {
             let path = self.folder_builder().unwrap().steps_path();
             fs::create_dir(&path)?;
-            solver.save_steps(&path);
+            solver
+                .save_steps(&path)
+                .step_image_format(self.options().image_format());
+        }
+        if solve_options.save_animation {
+            let path = self.folder_builder().unwrap().animation_path();
+            solver
+                .save_animation(&path)
+                .frame_delay(solve_options.frame_delay);
+        }
+        if solve_options.save_explanation {
+            let path = self.folder_builder().unwrap().explanation_path();
+            solver.save_explanation(&path);
         }
         Ok(solver)
     }
@@ -187,12 +554,25 @@ impl PuzzleContext<'_> {
         };
         let path = self.next_puzzle_path();
         folder_builder.save(&path)?;
-        println!("Saved puzzle to {}", path.display());
+        messageln!(self.options(), "Saved puzzle to {}", path.display());
         Ok(())
     }
 }
 
-fn print_puzzle(puzzle: &Puzzle) {
+/// The JSON document printed for each puzzle with `--output-format json`
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct PuzzleOutput<'a> {
+    puzzle: &'a Puzzle,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solve_result: Option<&'a SolveResult>,
+}
+
+fn print_puzzle(puzzle: &Puzzle, format: Format) {
+    if format == Format::Text {
+        print!("{}", PuzzleTextBuilder::new(puzzle).build());
+        return;
+    }
     let cages = puzzle
         .cages()
         .enumerate()
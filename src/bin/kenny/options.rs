@@ -1,17 +1,39 @@
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::ArgMatches;
 use kenny::collections::square::SquareValue;
+use kenny::generate::{GeneratorConfig, GeneratorConfigBuilder};
+use kenny::image::{ImageFormat, ImageTheme, PageSize};
+use kenny::puzzle::{CollectionFormat, Operator};
+use kenny::solve::DifficultyTier;
 
 const DEFAULT_PUZZLE_WIDTH: SquareValue = 4;
 const DEFAULT_PATH: &str = "output";
+/// The values of `--input-format` and `--output-format`
+#[cfg(feature = "json")]
+const DATA_FORMATS: &[&str] = &["text", "json"];
+#[cfg(not(feature = "json"))]
+const DATA_FORMATS: &[&str] = &["text"];
 
 #[derive(Clone)]
 pub(crate) struct Options {
     output_path: Option<PathBuf>,
     source: Source,
     solve: Option<Solve>,
+    count_solutions: Option<CountSolutions>,
+    check: Option<Check>,
+    booklet: Option<Booklet>,
+    save_collection: Option<PathBuf>,
+    report: Option<PathBuf>,
+    play: bool,
+    format: Format,
+    input_format: DataFormat,
+    output_format: DataFormat,
+    image_format: ImageFormat,
+    theme: ImageTheme,
     save_image: bool,
     save_puzzle: bool,
 }
@@ -23,10 +45,19 @@ impl Options {
 
     fn from_arg_matches(matches: &ArgMatches<'_>) -> Result<Self> {
         let save_all = matches.is_present("save_all");
+        if matches.occurrences_of("dpi") != 0 && matches.value_of("image_format") != Some("png") {
+            bail!("--dpi requires --image-format png");
+        }
+        let save_animation = matches.is_present("save_animation") || save_all;
+        if matches.occurrences_of("frame_delay") != 0 && !save_animation {
+            bail!("--frame-delay requires --save-animation or --save-all");
+        }
         let mut options = Self {
             output_path: None,
             source: if let Some(path) = matches.value_of("input") {
                 Source::File(path.into())
+            } else if let Some(path) = matches.value_of("collection") {
+                Source::Collection(path.into())
             } else {
                 let (include_solvable, include_unsolvable) =
                     if matches.is_present("allow_unsolvable") {
@@ -47,16 +78,77 @@ impl Options {
                     include_unsolvable,
                     require_search: matches.is_present("require_search"),
                     no_require_search: matches.is_present("no_require_search"),
+                    difficulty: matches
+                        .value_of("difficulty")
+                        .map(str::parse::<DifficultyTier>)
+                        .transpose()?,
+                    config: generator_config(matches)?,
                 })
             },
             solve: if matches.is_present("solve") {
                 Some(Solve {
                     save_image: matches.is_present("save_solved_image") || save_all,
                     save_step_images: matches.is_present("save_step_images") || save_all,
+                    save_explanation: matches.is_present("save_explanation") || save_all,
+                    save_animation,
+                    frame_delay: Duration::from_millis(
+                        parse_value(matches, "frame_delay")?.unwrap(),
+                    ),
+                })
+            } else {
+                None
+            },
+            count_solutions: if matches.is_present("count_solutions") {
+                Some(CountSolutions {
+                    limit: parse_value(matches, "solution_limit")?,
                 })
             } else {
                 None
             },
+            check: matches.value_of("check").map(|path| Check {
+                path: path.into(),
+                with_solution: matches.is_present("check_solution"),
+            }),
+            booklet: matches
+                .value_of("booklet")
+                .map(|path| -> Result<_> {
+                    let layout = matches.value_of("booklet_layout").unwrap();
+                    let (columns, rows) = parse_layout(layout)?;
+                    Ok(Booklet {
+                        path: path.into(),
+                        title: matches.value_of("booklet_title").unwrap().into(),
+                        columns,
+                        rows,
+                        page_size: match matches.value_of("page_size") {
+                            Some("a4") => PageSize::A4,
+                            _ => PageSize::Letter,
+                        },
+                    })
+                })
+                .transpose()?,
+            save_collection: matches.value_of("save_collection").map(PathBuf::from),
+            report: matches.value_of("report").map(PathBuf::from),
+            play: matches.is_present("play"),
+            image_format: match matches.value_of("image_format") {
+                Some("png") => ImageFormat::Png {
+                    dpi: parse_value(matches, "dpi")?.unwrap(),
+                },
+                _ => ImageFormat::Svg,
+            },
+            theme: match matches.value_of("theme") {
+                None => ImageTheme::default(),
+                Some(name) => match ImageTheme::preset(name) {
+                    Some(theme) => theme,
+                    None => ImageTheme::from_file(Path::new(name))
+                        .with_context(|| format!("Error reading theme from {}", name))?,
+                },
+            },
+            format: match matches.value_of("format") {
+                Some("text") => Format::Text,
+                _ => Format::Cages,
+            },
+            input_format: data_format(matches, "input_format"),
+            output_format: data_format(matches, "output_format"),
             save_image: matches.is_present("save_image") || save_all,
             save_puzzle: matches.is_present("save_puzzle") || save_all,
         };
@@ -74,7 +166,7 @@ impl Options {
             return true;
         }
         if let Some(ref sc) = &self.solve {
-            if sc.save_image || sc.save_step_images {
+            if sc.save_image || sc.save_step_images || sc.save_explanation || sc.save_animation {
                 return true;
             }
         }
@@ -93,6 +185,52 @@ impl Options {
         self.solve.as_ref()
     }
 
+    pub fn count_solutions(&self) -> Option<&CountSolutions> {
+        self.count_solutions.as_ref()
+    }
+
+    pub fn check(&self) -> Option<&Check> {
+        self.check.as_ref()
+    }
+
+    pub fn booklet(&self) -> Option<&Booklet> {
+        self.booklet.as_ref()
+    }
+
+    /// The path to save every puzzle to a collection
+    pub fn save_collection(&self) -> Option<&Path> {
+        self.save_collection.as_deref()
+    }
+
+    /// The path to save a summary of the solve results
+    pub fn report(&self) -> Option<&Path> {
+        self.report.as_deref()
+    }
+
+    pub fn image_format(&self) -> ImageFormat {
+        self.image_format
+    }
+
+    pub fn theme(&self) -> &ImageTheme {
+        &self.theme
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn input_format(&self) -> DataFormat {
+        self.input_format
+    }
+
+    pub fn output_format(&self) -> DataFormat {
+        self.output_format
+    }
+
+    pub fn play(&self) -> bool {
+        self.play
+    }
+
     pub fn save_image(&self) -> bool {
         self.save_image
     }
@@ -105,6 +243,8 @@ impl Options {
 #[derive(Clone)]
 pub(crate) enum Source {
     File(PathBuf),
+    /// A file with many puzzles
+    Collection(PathBuf),
     Generate(Generate),
 }
 
@@ -125,12 +265,163 @@ pub(crate) struct Generate {
     pub include_unsolvable: bool,
     pub require_search: bool,
     pub no_require_search: bool,
+    pub difficulty: Option<DifficultyTier>,
+    pub config: GeneratorConfig,
 }
 
 #[derive(Clone)]
 pub(crate) struct Solve {
     pub save_image: bool,
     pub save_step_images: bool,
+    pub save_explanation: bool,
+    pub save_animation: bool,
+    /// How long each frame of the animation is shown
+    pub frame_delay: Duration,
+}
+
+#[derive(Clone)]
+pub(crate) struct CountSolutions {
+    pub limit: Option<usize>,
+}
+
+/// How puzzles and solutions are printed
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+    /// The cage index of each cell and a list of cages
+    Cages,
+    /// A drawing with cage borders
+    Text,
+}
+
+/// The file format of puzzles that are read, and of puzzles and results that are written
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum DataFormat {
+    Text,
+    /// A JSON document for each puzzle. Other messages are printed to stderr.
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl DataFormat {
+    /// The format of collections, with one JSON document on each line for JSON
+    pub fn collection_format(self) -> CollectionFormat {
+        match self {
+            DataFormat::Text => CollectionFormat::Text,
+            #[cfg(feature = "json")]
+            DataFormat::Json => CollectionFormat::JsonLines,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Check {
+    pub path: PathBuf,
+    pub with_solution: bool,
+}
+
+/// Saves puzzles to a PDF booklet
+#[derive(Clone)]
+pub(crate) struct Booklet {
+    pub path: PathBuf,
+    pub title: String,
+    pub columns: u32,
+    pub rows: u32,
+    pub page_size: PageSize,
+}
+
+fn data_format(matches: &ArgMatches<'_>, name: &str) -> DataFormat {
+    match matches.value_of(name) {
+        #[cfg(feature = "json")]
+        Some("json") => DataFormat::Json,
+        _ => DataFormat::Text,
+    }
+}
+
+fn generator_config(matches: &ArgMatches<'_>) -> Result<GeneratorConfig> {
+    let mut builder = GeneratorConfigBuilder::new();
+    if let Some(operators) = matches.value_of("operators") {
+        builder.operators(&parse_operators(operators)?);
+    }
+    if let Some(weights) = matches.value_of("operator_weights") {
+        builder.operators(&[]);
+        for (operator, weight) in parse_operator_weights(weights)? {
+            builder.operator_weight(operator, weight);
+        }
+    }
+    if let Some(size) = parse_value(matches, "max_cage_size")? {
+        builder.max_cage_size(size);
+    }
+    if let Some(size) = parse_value(matches, "average_cage_size")? {
+        builder.average_cage_size(size);
+    }
+    if let Some(distribution) = parse_value(matches, "cage_size_distribution")? {
+        builder.cage_size_distribution(distribution);
+    }
+    if let Some(count) = parse_value(matches, "max_single_cell_cages")? {
+        builder.max_single_cell_cages(count);
+    }
+    if let Some(seed) = parse_value(matches, "seed")? {
+        builder.seed(seed);
+    }
+    builder.hide_operators(matches.is_present("hide_operators"));
+    let config = builder.build()?;
+    Ok(config)
+}
+
+fn parse_value<T>(matches: &ArgMatches<'_>, name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    matches
+        .value_of(name)
+        .map(|s| {
+            s.parse::<T>()
+                .with_context(|| format!("invalid value for {}: \"{}\"", name, s))
+        })
+        .transpose()
+}
+
+/// Parses the number of columns and rows of puzzles on a page, such as `2x3`
+fn parse_layout(s: &str) -> Result<(u32, u32)> {
+    let invalid = || format!("invalid booklet layout: \"{}\"", s);
+    let (columns, rows) = s.split_once('x').with_context(invalid)?;
+    let columns: u32 = columns.trim().parse().with_context(invalid)?;
+    let rows: u32 = rows.trim().parse().with_context(invalid)?;
+    if columns == 0 || rows == 0 {
+        bail!(invalid());
+    }
+    Ok((columns, rows))
+}
+
+/// Parses a comma-separated list of operator symbols
+fn parse_operators(s: &str) -> Result<Vec<Operator>> {
+    s.split(',').map(parse_operator).collect()
+}
+
+/// Parses a comma-separated list of operator symbols with weights, such as `+:2,*:1`
+fn parse_operator_weights(s: &str) -> Result<Vec<(Operator, u32)>> {
+    s.split(',')
+        .map(|item| {
+            let (symbol, weight) = item
+                .split_once(':')
+                .with_context(|| format!("invalid operator weight: \"{}\"", item))?;
+            let weight = weight
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid operator weight: \"{}\"", item))?;
+            Ok((parse_operator(symbol)?, weight))
+        })
+        .collect()
+}
+
+fn parse_operator(symbol: &str) -> Result<Operator> {
+    let mut chars = symbol.trim().chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Operator::from_symbol(c),
+        _ => None,
+    }
+    .with_context(|| format!("invalid operator: \"{}\"", symbol))
 }
 
 fn clap_app() -> clap::App<'static, 'static> {
@@ -141,10 +432,10 @@ fn clap_app() -> clap::App<'static, 'static> {
         .help_message("Solve KenKen Puzzles")
         .setting(AppSettings::ArgRequiredElseHelp)
         // can use in clap 3.0 when released
-        // .replace("--save-all", &["--save-puzzle", "--save-image", "--save-solved-image", "--save-step-images"])
+        // .replace("--save-all", &["--save-puzzle", "--save-image", "--save-solved-image", "--save-step-images", "--save-explanation"])
         .group(
             ArgGroup::with_name("source")
-                .args(&["generate", "input"])
+                .args(&["generate", "input", "collection"])
                 .required(true),
         )
         .arg(
@@ -163,12 +454,151 @@ fn clap_app() -> clap::App<'static, 'static> {
                 .help("read a KenKen puzzle from a file")
                 .display_order(1),
         )
+        .arg(
+            Arg::with_name("collection")
+                .long("collection")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("read every puzzle in a collection file, in the input format")
+                .display_order(1),
+        )
+        .arg(
+            Arg::with_name("save_collection")
+                .long("save-collection")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("save every puzzle to a collection file, in the output format"),
+        )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .requires("solve")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("save a summary of the solve results of every puzzle"),
+        )
         .arg(
             Arg::with_name("solve")
                 .short("s")
                 .long("solve")
                 .help("solve KenKen puzzle(s)"),
         )
+        .arg(
+            Arg::with_name("count_solutions")
+                .long("count-solutions")
+                .help("find and print every solution of the puzzle(s)"),
+        )
+        .arg(
+            Arg::with_name("solution_limit")
+                .long("solution-limit")
+                .requires("count_solutions")
+                .takes_value(true)
+                .value_name("COUNT")
+                .help("stop counting solutions after this many are found"),
+        )
+        .arg(
+            Arg::with_name("check")
+                .long("check")
+                .requires("input")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("check a partially filled grid from a file for conflicts with the puzzle"),
+        )
+        .arg(
+            Arg::with_name("check_solution")
+                .long("check-solution")
+                .requires("check")
+                .help("also report entries of the checked grid that differ from the solution"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["cages", "text"])
+                .default_value("cages")
+                .help("how to print puzzles and solutions"),
+        )
+        .arg(
+            Arg::with_name("input_format")
+                .long("input-format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(DATA_FORMATS)
+                .default_value("text")
+                .help("the file format of the input puzzle or collection"),
+        )
+        .arg(
+            Arg::with_name("output_format")
+                .long("output-format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(DATA_FORMATS)
+                .default_value("text")
+                .help("print a JSON document for each puzzle and save puzzles as JSON"),
+        )
+        .arg(
+            Arg::with_name("image_format")
+                .long("image-format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["svg", "png"])
+                .default_value("svg")
+                .help("the file format of saved images"),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .long("theme")
+                .takes_value(true)
+                .value_name("THEME")
+                .help("the style of images: dark, high-contrast or the path of a theme file"),
+        )
+        .arg(
+            Arg::with_name("dpi")
+                .long("dpi")
+                .takes_value(true)
+                .value_name("DPI")
+                .default_value("96")
+                .help("the resolution of PNG images, with --image-format png"),
+        )
+        .arg(
+            Arg::with_name("booklet")
+                .long("booklet")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("save the puzzle(s) to a PDF booklet with answer pages at the back"),
+        )
+        .arg(
+            Arg::with_name("booklet_title")
+                .long("booklet-title")
+                .takes_value(true)
+                .value_name("TITLE")
+                .default_value("KenKen Puzzles")
+                .help("the title printed at the top of each booklet page"),
+        )
+        .arg(
+            Arg::with_name("booklet_layout")
+                .long("booklet-layout")
+                .takes_value(true)
+                .value_name("COLSxROWS")
+                .default_value("2x2")
+                .validator(|s| parse_layout(&s).map(|_| ()).map_err(|e| e.to_string()))
+                .help("the number of puzzles across and down each booklet page"),
+        )
+        .arg(
+            Arg::with_name("page_size")
+                .long("page-size")
+                .takes_value(true)
+                .value_name("SIZE")
+                .possible_values(&["letter", "a4"])
+                .default_value("letter")
+                .help("the page size of the booklet"),
+        )
+        .arg(
+            Arg::with_name("play")
+                .long("play")
+                .help("play the puzzle(s) interactively in the terminal"),
+        )
         .arg(
             Arg::with_name("width")
                 .short("w")
@@ -206,6 +636,85 @@ fn clap_app() -> clap::App<'static, 'static> {
                 .conflicts_with("require_search")
                 .help("only include puzzles that do not require backtracking search to solve"),
         )
+        .arg(
+            Arg::with_name("difficulty")
+                .long("difficulty")
+                .short("d")
+                .requires("generate")
+                .takes_value(true)
+                .value_name("TIER")
+                .possible_values(&["easy", "medium", "hard", "expert"])
+                .conflicts_with_all(&[
+                    "require_search",
+                    "no_require_search",
+                    "allow_unsolvable",
+                    "unsolvable_only",
+                ])
+                .help("only generate puzzles with the given difficulty"),
+        )
+        .arg(
+            Arg::with_name("operators")
+                .long("operators")
+                .requires("generate")
+                .takes_value(true)
+                .value_name("OPERATORS")
+                .help("comma-separated list of operators to use in generated puzzles (e.g. +,-)"),
+        )
+        .arg(
+            Arg::with_name("operator_weights")
+                .long("operator-weights")
+                .requires("generate")
+                .conflicts_with("operators")
+                .takes_value(true)
+                .value_name("WEIGHTS")
+                .help("comma-separated list of operators with relative weights (e.g. +:2,*:2,-:1)"),
+        )
+        .arg(
+            Arg::with_name("max_cage_size")
+                .long("max-cage-size")
+                .requires("generate")
+                .takes_value(true)
+                .value_name("SIZE")
+                .help("the maximum number of cells in a generated cage"),
+        )
+        .arg(
+            Arg::with_name("average_cage_size")
+                .long("average-cage-size")
+                .requires("generate")
+                .takes_value(true)
+                .value_name("SIZE")
+                .help("the average number of cells in generated cages"),
+        )
+        .arg(
+            Arg::with_name("cage_size_distribution")
+                .long("cage-size-distribution")
+                .requires("generate")
+                .takes_value(true)
+                .value_name("RATIO")
+                .help("higher values (up to 1) generate more small cages"),
+        )
+        .arg(
+            Arg::with_name("max_single_cell_cages")
+                .long("max-single-cell-cages")
+                .requires("generate")
+                .takes_value(true)
+                .value_name("COUNT")
+                .help("the maximum number of single cell cages in a generated puzzle"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .requires("generate")
+                .takes_value(true)
+                .value_name("SEED")
+                .help("seed of the first generated puzzle"),
+        )
+        .arg(
+            Arg::with_name("hide_operators")
+                .long("hide-operators")
+                .requires("generate")
+                .help("hide the operators of generated cages (no-op puzzle)"),
+        )
         .arg(
             Arg::with_name("allow_unsolvable")
                 .long("allow-unsolvable")
@@ -245,4 +754,24 @@ fn clap_app() -> clap::App<'static, 'static> {
                 .long("save-step-images")
                 .help("save an image of the puzzle at each step of the solving process"),
         )
+        .arg(
+            Arg::with_name("save_explanation")
+                .long("save-explanation")
+                .requires("solve")
+                .help("save a numbered, plain-English explanation of each step of the solving process"),
+        )
+        .arg(
+            Arg::with_name("save_animation")
+                .long("save-animation")
+                .requires("solve")
+                .help("save an animated GIF of the solving process, including backtracking"),
+        )
+        .arg(
+            Arg::with_name("frame_delay")
+                .long("frame-delay")
+                .takes_value(true)
+                .value_name("MS")
+                .default_value("500")
+                .help("how long each frame of the animation is shown, in milliseconds"),
+        )
 }
@@ -0,0 +1,260 @@
+use kenny::puzzle::{CellId, Conflict, PlayerGrid, Puzzle, Value};
+use kenny::solve::PuzzleSolver;
+
+/// The state of an interactive game: the player's grid, the cursor and the undo history
+pub(crate) struct Game<'a> {
+    puzzle: &'a Puzzle,
+    grid: PlayerGrid,
+    cursor: CellId,
+    pencil_mode: bool,
+    undo: Vec<PlayerGrid>,
+    redo: Vec<PlayerGrid>,
+    /// Conflicts found by the last check, cleared when the grid changes
+    conflicts: Vec<Conflict>,
+    /// Cells changed by the last hint, cleared when the grid changes
+    hint_cells: Vec<CellId>,
+    message: String,
+}
+
+impl<'a> Game<'a> {
+    pub fn new(puzzle: &'a Puzzle) -> Self {
+        Self {
+            puzzle,
+            grid: PlayerGrid::new(puzzle.width()),
+            cursor: 0,
+            pencil_mode: false,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            conflicts: Vec::new(),
+            hint_cells: Vec::new(),
+            message: String::new(),
+        }
+    }
+
+    pub fn puzzle(&self) -> &'a Puzzle {
+        self.puzzle
+    }
+
+    pub fn grid(&self) -> &PlayerGrid {
+        &self.grid
+    }
+
+    pub fn cursor(&self) -> CellId {
+        self.cursor
+    }
+
+    pub fn pencil_mode(&self) -> bool {
+        self.pencil_mode
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    pub fn hint_cells(&self) -> &[CellId] {
+        &self.hint_cells
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Moves the cursor by a number of columns and rows, wrapping around the edges
+    pub fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let width = self.puzzle.width() as i32;
+        let col = (self.cursor as i32 % width + dx).rem_euclid(width);
+        let row = (self.cursor as i32 / width + dy).rem_euclid(width);
+        self.cursor = (row * width + col) as CellId;
+    }
+
+    pub fn toggle_pencil_mode(&mut self) {
+        self.pencil_mode = !self.pencil_mode;
+    }
+
+    /// Enters a value in the cell at the cursor, or toggles a pencil mark in pencil mode
+    pub fn enter(&mut self, value: Value) {
+        if value < 1 || value > self.puzzle.width() as Value {
+            return;
+        }
+        let cell = self.cursor;
+        if self.pencil_mode {
+            if self.grid.entry(cell).is_some() {
+                return;
+            }
+            let mut marks = self.grid.pencil_marks(cell).to_vec();
+            match marks.iter().position(|&mark| mark == value) {
+                Some(i) => {
+                    marks.remove(i);
+                }
+                None => {
+                    marks.push(value);
+                    marks.sort_unstable();
+                }
+            }
+            self.edit(|grid| {
+                grid.set_pencil_marks(cell, &marks);
+            });
+        } else {
+            if self.grid.entry(cell) == Some(value) {
+                return;
+            }
+            self.edit(|grid| {
+                grid.set_entry(cell, Some(value));
+            });
+            if self.is_solved() {
+                self.message = "Solved!".into();
+            }
+        }
+    }
+
+    /// Clears the entry of the cell at the cursor, or its pencil marks if it has no entry
+    pub fn clear(&mut self) {
+        let cell = self.cursor;
+        if self.grid.entry(cell).is_some() {
+            self.edit(|grid| {
+                grid.set_entry(cell, None);
+            });
+        } else if !self.grid.pencil_marks(cell).is_empty() {
+            self.edit(|grid| {
+                grid.set_pencil_marks(cell, &[]);
+            });
+        }
+    }
+
+    pub fn undo(&mut self) {
+        match self.undo.pop() {
+            None => self.message = "Nothing to undo".into(),
+            Some(grid) => {
+                self.redo.push(std::mem::replace(&mut self.grid, grid));
+                self.on_grid_changed();
+            }
+        }
+    }
+
+    pub fn redo(&mut self) {
+        match self.redo.pop() {
+            None => self.message = "Nothing to redo".into(),
+            Some(grid) => {
+                self.undo.push(std::mem::replace(&mut self.grid, grid));
+                self.on_grid_changed();
+            }
+        }
+    }
+
+    /// Checks the grid for conflicts and marks them
+    pub fn check(&mut self) {
+        self.hint_cells.clear();
+        self.conflicts = match self.puzzle.check_grid(&self.grid) {
+            Ok(conflicts) => conflicts,
+            Err(e) => {
+                self.message = format!("Cannot check the grid: {}", e);
+                return;
+            }
+        };
+        self.message = match self.conflicts.len() {
+            0 => "No conflicts found".into(),
+            1 => format!("Conflict: {}", self.conflicts[0].describe(self.puzzle)),
+            n => format!(
+                "{} conflicts. {}",
+                n,
+                self.conflicts[0].describe(self.puzzle)
+            ),
+        };
+    }
+
+    /// Asks the solver for the next deduction and marks the cells that it changes
+    pub fn hint(&mut self) {
+        self.conflicts.clear();
+        self.hint_cells.clear();
+        match PuzzleSolver::new(self.puzzle).hint(&self.grid) {
+            Ok(Some(deduction)) => {
+                self.message = deduction.description;
+                self.hint_cells = deduction.cells;
+            }
+            Ok(None) if self.grid.entries().iter().all(Option::is_some) => {
+                self.message = "The grid is complete".into();
+            }
+            Ok(None) => self.message = "No hint without guessing".into(),
+            Err(e) => self.message = format!("No hint: {}", e),
+        }
+    }
+
+    /// True if every cell has an entry and there are no conflicts
+    pub fn is_solved(&self) -> bool {
+        self.grid.entries().iter().all(Option::is_some)
+            && self
+                .puzzle
+                .check_grid(&self.grid)
+                .is_ok_and(|conflicts| conflicts.is_empty())
+    }
+
+    fn edit(&mut self, f: impl FnOnce(&mut PlayerGrid)) {
+        self.undo.push(self.grid.clone());
+        self.redo.clear();
+        f(&mut self.grid);
+        self.on_grid_changed();
+    }
+
+    fn on_grid_changed(&mut self) {
+        self.conflicts.clear();
+        self.hint_cells.clear();
+        self.message.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kenny::puzzle::Puzzle;
+
+    use crate::play::game::Game;
+
+    fn puzzle() -> Puzzle {
+        Puzzle::parse("2\nAB\nCC\n1 2 3+").unwrap()
+    }
+
+    #[test]
+    fn undo_redo() {
+        let puzzle = puzzle();
+        let mut game = Game::new(&puzzle);
+        game.enter(1);
+        game.move_cursor(1, 0);
+        game.toggle_pencil_mode();
+        game.enter(2);
+        game.enter(1);
+        assert_eq!(&[1, 2], game.grid().pencil_marks(1));
+        game.undo();
+        game.undo();
+        assert!(game.grid().pencil_marks(1).is_empty());
+        game.redo();
+        assert_eq!(&[2], game.grid().pencil_marks(1));
+        game.toggle_pencil_mode();
+        game.enter(2);
+        // redo history is discarded by a new edit
+        game.redo();
+        assert_eq!(Some(2), game.grid().entry(1));
+    }
+
+    #[test]
+    fn move_cursor_wraps() {
+        let puzzle = puzzle();
+        let mut game = Game::new(&puzzle);
+        game.move_cursor(-1, 0);
+        assert_eq!(1, game.cursor());
+        game.move_cursor(0, 1);
+        assert_eq!(3, game.cursor());
+        game.move_cursor(0, 1);
+        assert_eq!(1, game.cursor());
+    }
+
+    #[test]
+    fn solve() {
+        let puzzle = puzzle();
+        let mut game = Game::new(&puzzle);
+        for (dx, dy, value) in [(0, 0, 1), (1, 0, 2), (1, 1, 2), (1, 0, 1)] {
+            game.move_cursor(dx, dy);
+            game.enter(value);
+        }
+        assert!(game.is_solved());
+        assert_eq!("Solved!", game.message());
+    }
+}
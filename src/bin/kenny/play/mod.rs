@@ -0,0 +1,102 @@
+//! Play a puzzle interactively in a terminal
+
+use std::io;
+use std::io::Write;
+
+use anyhow::{bail, Context, Result};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{event, terminal};
+use kenny::collections::square::SquareValue;
+use kenny::puzzle::{Puzzle, Value};
+
+use crate::play::game::Game;
+use crate::play::render::render;
+
+mod game;
+mod render;
+
+const CLEAR: &str = "\x1b[H\x1b[2J";
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+const LEAVE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+
+/// The widest puzzle that can be played, since values are entered with a single key
+const MAX_PLAY_WIDTH: SquareValue = 9;
+
+/// Plays a puzzle until the player quits, then prints the grid
+pub(crate) fn play(puzzle: &Puzzle) -> Result<()> {
+    if puzzle.width() > MAX_PLAY_WIDTH {
+        bail!(
+            "Play mode supports puzzles up to {} wide, but the puzzle is {} wide",
+            MAX_PLAY_WIDTH,
+            puzzle.width()
+        );
+    }
+    let mut game = Game::new(puzzle);
+    {
+        let _screen = Screen::enter()?;
+        let mut out = io::stdout();
+        loop {
+            write!(out, "{}{}", CLEAR, render(&game))?;
+            out.flush()?;
+            let key = match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => key,
+                _ => continue,
+            };
+            if !on_key(&mut game, key) {
+                break;
+            }
+        }
+    }
+    println!("{}", game.grid());
+    if game.is_solved() {
+        println!("Solved!");
+    }
+    Ok(())
+}
+
+/// Handles a key press. Returns false to quit.
+fn on_key(game: &mut Game<'_>, key: KeyEvent) -> bool {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('c') if ctrl => return false,
+        KeyCode::Char('z') if ctrl => game.undo(),
+        KeyCode::Char('y') if ctrl => game.redo(),
+        KeyCode::Char('q') | KeyCode::Esc => return false,
+        KeyCode::Left => game.move_cursor(-1, 0),
+        KeyCode::Right => game.move_cursor(1, 0),
+        KeyCode::Up => game.move_cursor(0, -1),
+        KeyCode::Down => game.move_cursor(0, 1),
+        KeyCode::Char(c @ '1'..='9') => game.enter(c as Value - '0' as Value),
+        KeyCode::Char('0' | ' ' | '.') | KeyCode::Backspace | KeyCode::Delete => game.clear(),
+        KeyCode::Char('p') => game.toggle_pencil_mode(),
+        KeyCode::Char('u') => game.undo(),
+        KeyCode::Char('r') => game.redo(),
+        KeyCode::Char('c') => game.check(),
+        KeyCode::Char('h') => game.hint(),
+        _ => {}
+    }
+    true
+}
+
+/// Puts the terminal in raw mode on an alternate screen until dropped
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode().context("play mode requires a terminal")?;
+        let screen = Self;
+        let mut out = io::stdout();
+        write!(out, "{}", ENTER_SCREEN)?;
+        out.flush()?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        let _ = write!(out, "{}", LEAVE_SCREEN);
+        let _ = out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
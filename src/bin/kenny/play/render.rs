@@ -0,0 +1,116 @@
+//! Draw a game as text with ANSI escape sequences
+
+use kenny::puzzle::CellId;
+use kenny::text::{cage_label, cage_labels, draw_grid};
+
+use crate::play::game::Game;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const STYLE_CURSOR: &str = "\x1b[7m";
+const STYLE_CONFLICT: &str = "\x1b[41;97m";
+const STYLE_HINT: &str = "\x1b[43;30m";
+
+/// The lines of text in a cell, below the top border
+const CELL_HEIGHT: usize = 3;
+const MIN_CELL_WIDTH: usize = 5;
+
+const HELP: &str =
+    "arrows: move  1-9: enter  p: pencil  backspace: clear  u/r: undo/redo  c: check  h: hint  q: quit";
+
+/// Draws the grid, a status line and help. Lines end with "\r\n" for a terminal in raw mode.
+pub(crate) fn render(game: &Game<'_>) -> String {
+    let puzzle = game.puzzle();
+    let labels = cage_labels(puzzle);
+    let cell_width = labels
+        .iter()
+        .map(|label| label.chars().count())
+        .chain([puzzle.width() as usize, MIN_CELL_WIDTH])
+        .max()
+        .unwrap();
+    let mut lines = draw_grid(puzzle, cell_width, CELL_HEIGHT, |cell_id, line| {
+        cell_line(game, &labels, cell_id, line, cell_width)
+    });
+    lines.push(String::new());
+    let mode = if game.pencil_mode() {
+        "pencil"
+    } else {
+        "value"
+    };
+    lines.push(format!("Mode: {}  {}", mode, game.message()));
+    lines.push(format!("{}{}{}", DIM, HELP, RESET));
+    lines.join("\r\n")
+}
+
+/// One line of text inside a cell: the cage label, the entry or the pencil marks
+fn cell_line(
+    game: &Game<'_>,
+    labels: &[String],
+    cell_id: CellId,
+    line: usize,
+    cell_width: usize,
+) -> String {
+    let puzzle = game.puzzle();
+    let grid = game.grid();
+    let (text, style) = match line {
+        0 => {
+            let label = cage_label(puzzle, labels, cell_id);
+            (format!("{:<w$}", label, w = cell_width), "")
+        }
+        1 => {
+            let entry = grid.entry(cell_id).map_or(String::new(), |v| v.to_string());
+            (format!("{:^w$}", entry, w = cell_width), BOLD)
+        }
+        _ => {
+            let marks: String = grid
+                .pencil_marks(cell_id)
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            (format!("{:^w$}", marks, w = cell_width), DIM)
+        }
+    };
+    let background = if cell_id == game.cursor() {
+        STYLE_CURSOR
+    } else if game
+        .conflicts()
+        .iter()
+        .any(|conflict| conflict.cell_ids(puzzle).contains(&cell_id))
+    {
+        STYLE_CONFLICT
+    } else if game.hint_cells().contains(&cell_id) {
+        STYLE_HINT
+    } else {
+        ""
+    };
+    if background.is_empty() && style.is_empty() {
+        text
+    } else {
+        format!("{}{}{}{}", background, style, text, RESET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kenny::puzzle::Puzzle;
+
+    use crate::play::game::Game;
+    use crate::play::render::render;
+
+    #[test]
+    fn grid() {
+        let puzzle = Puzzle::parse("2\nAB\nCC\n1 2 3+").unwrap();
+        let mut game = Game::new(&puzzle);
+        game.move_cursor(0, 1);
+        game.enter(2);
+        let text = render(&game);
+        let lines: Vec<&str> = text.split("\r\n").collect();
+        assert_eq!("┌─────┬─────┐", lines[0]);
+        assert_eq!("│1    │2    │", lines[1]);
+        assert_eq!("├─────┴─────┤", lines[4]);
+        assert_eq!("│\x1b[7m3+   \x1b[0m┆     │", lines[5]);
+        assert!(lines[6].contains("\x1b[7m\x1b[1m  2  \x1b[0m"));
+        assert_eq!("└───────────┘", lines[8]);
+    }
+}
@@ -2,21 +2,23 @@ use std::path::{Path, PathBuf};
 use std::{fs, io};
 
 use anyhow::{Context, Result};
-use kenny::image::PuzzleImage;
+use kenny::image::{ImageFormat, PuzzleImage};
 use kenny::puzzle::Puzzle;
 use tempfile::{tempdir, TempDir};
 
-const IMG_EXT: &str = "svg";
+use crate::options::DataFormat;
 
 pub(crate) struct PuzzleFolderBuilder {
     temp_dir: TempDir,
+    image_format: ImageFormat,
     saved: bool,
 }
 
 impl PuzzleFolderBuilder {
-    pub fn new() -> io::Result<Self> {
+    pub fn new(image_format: ImageFormat) -> io::Result<Self> {
         let s = Self {
             temp_dir: tempdir()?,
+            image_format,
             saved: false,
         };
         Ok(s)
@@ -32,16 +34,43 @@ impl PuzzleFolderBuilder {
         self.temp_dir.path().join("steps")
     }
 
-    pub fn write_puzzle(&self, puzzle: &Puzzle) -> Result<()> {
-        let path = self.temp_dir.path().join("puzzle");
-        fs::write(&path, &puzzle.to_string().into_bytes())
+    pub fn explanation_path(&self) -> PathBuf {
+        self.temp_dir.path().join("explanation.txt")
+    }
+
+    pub fn animation_path(&self) -> PathBuf {
+        self.temp_dir.path().join("solve.gif")
+    }
+
+    /// Writes the puzzle to "puzzle" in the text format or "puzzle.json" in JSON
+    pub fn write_puzzle(&self, puzzle: &Puzzle, format: DataFormat) -> Result<()> {
+        let (name, contents) = match format {
+            DataFormat::Text => ("puzzle", puzzle.to_string()),
+            #[cfg(feature = "json")]
+            DataFormat::Json => ("puzzle.json", puzzle.to_json()),
+        };
+        let path = self.temp_dir.path().join(name);
+        fs::write(&path, contents.into_bytes())
             .with_context(|| format!("Error saving puzzle to {}", path.display()))?;
         Ok(())
     }
 
+    /// Records the seed that generated the puzzle
+    pub fn write_seed(&self, seed: u64) -> Result<()> {
+        let path = self.temp_dir.path().join("seed");
+        fs::write(&path, format!("{}\n", seed))
+            .with_context(|| format!("Error saving seed to {}", path.display()))?;
+        Ok(())
+    }
+
     pub fn write_puzzle_image(&self, image: &PuzzleImage<'_>) -> Result<()> {
-        let path = self.temp_dir.path().join(format!("image.{}", IMG_EXT));
-        image.save_svg(&path).context("error saving puzzle image")?;
+        let path = self
+            .temp_dir
+            .path()
+            .join(format!("image.{}", self.image_format.extension()));
+        image
+            .save(&path, self.image_format)
+            .context("error saving puzzle image")?;
         Ok(())
     }
 
@@ -49,12 +78,37 @@ impl PuzzleFolderBuilder {
         let path = self
             .temp_dir
             .path()
-            .join(format!("image_solved.{}", IMG_EXT));
+            .join(format!("image_solved.{}", self.image_format.extension()));
         image
-            .save_svg(&path)
+            .save(&path, self.image_format)
             .context("error saving solved puzzle image")?;
         Ok(())
     }
+
+    /// Writes an image of a checked grid with its conflicts marked
+    pub fn write_checked_grid_image(&self, image: &PuzzleImage<'_>) -> Result<()> {
+        let path = self
+            .temp_dir
+            .path()
+            .join(format!("image_checked.{}", self.image_format.extension()));
+        image
+            .save(&path, self.image_format)
+            .context("error saving checked grid image")?;
+        Ok(())
+    }
+
+    /// Writes an image of one of multiple solutions. `n` distinguishes the solutions.
+    pub fn write_multiple_solutions_image(&self, n: usize, image: &PuzzleImage<'_>) -> Result<()> {
+        let path = self.temp_dir.path().join(format!(
+            "image_solution_{}.{}",
+            n,
+            self.image_format.extension()
+        ));
+        image
+            .save(&path, self.image_format)
+            .context("error saving multiple solutions image")?;
+        Ok(())
+    }
 }
 
 impl Drop for PuzzleFolderBuilder {
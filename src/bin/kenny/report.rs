@@ -0,0 +1,93 @@
+use std::fmt::{self, Display, Formatter};
+
+use kenny::puzzle::Puzzle;
+use kenny::solve::{Difficulty, DifficultyTier, SolveResult};
+
+/// A summary of the solve results of every puzzle
+#[derive(Default)]
+pub(crate) struct SolveReport {
+    entries: Vec<(String, Outcome)>,
+}
+
+enum Outcome {
+    Unsolvable,
+    Solved(Difficulty),
+    MultipleSolutions,
+    /// The puzzle could not be read, for the given reason
+    Invalid(String),
+}
+
+impl SolveReport {
+    /// Adds a result, naming the puzzle by its title or its index, which counts from 1
+    pub fn add(&mut self, index: usize, puzzle: &Puzzle, result: &SolveResult) {
+        let name = match puzzle.metadata().get("title") {
+            Some(title) => title.to_string(),
+            None => format!("Puzzle {}", index),
+        };
+        let outcome = match result {
+            SolveResult::Unsolvable => Outcome::Unsolvable,
+            SolveResult::Solved(data) => Outcome::Solved(data.difficulty),
+            SolveResult::MultipleSolutions(_) => Outcome::MultipleSolutions,
+        };
+        self.entries.push((name, outcome));
+    }
+
+    /// Adds a puzzle that could not be read
+    pub fn add_invalid(&mut self, index: usize, reason: String) {
+        self.entries
+            .push((format!("Puzzle {}", index), Outcome::Invalid(reason)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn count(&self, f: impl Fn(&Outcome) -> bool) -> usize {
+        self.entries
+            .iter()
+            .filter(|(_, outcome)| f(outcome))
+            .count()
+    }
+}
+
+impl Display for SolveReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (name, outcome) in &self.entries {
+            match outcome {
+                Outcome::Unsolvable => writeln!(f, "{}: not solvable", name)?,
+                Outcome::Solved(difficulty) => writeln!(f, "{}: solved, {}", name, difficulty)?,
+                Outcome::MultipleSolutions => writeln!(f, "{}: multiple solutions", name)?,
+                Outcome::Invalid(reason) => writeln!(f, "{}: invalid ({})", name, reason)?,
+            }
+        }
+        writeln!(f)?;
+        writeln!(f, "Puzzles: {}", self.entries.len())?;
+        writeln!(
+            f,
+            "Solved: {}",
+            self.count(|o| matches!(o, Outcome::Solved(_)))
+        )?;
+        for tier in DifficultyTier::ALL {
+            let count = self.count(|o| matches!(o, Outcome::Solved(d) if d.tier() == tier));
+            if count > 0 {
+                writeln!(f, "  {}: {}", tier, count)?;
+            }
+        }
+        writeln!(
+            f,
+            "Not solvable: {}",
+            self.count(|o| matches!(o, Outcome::Unsolvable))
+        )?;
+        writeln!(
+            f,
+            "Multiple solutions: {}",
+            self.count(|o| matches!(o, Outcome::MultipleSolutions))
+        )?;
+        writeln!(
+            f,
+            "Invalid: {}",
+            self.count(|o| matches!(o, Outcome::Invalid(_)))
+        )?;
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::hash::{BuildHasher, Hash};
 use std::iter::Peekable;
 
+#[allow(dead_code)]
 pub(crate) trait IteratorExt: Iterator + Sized {
     fn add_to<E>(self, e: &mut E)
     where
@@ -12,7 +13,7 @@ pub(crate) trait IteratorExt: Iterator + Sized {
         e.extend(self);
     }
 
-    fn collect_into<E>(self, mut e: E) -> E
+    fn collect_to<E>(self, mut e: E) -> E
     where
         E: Extend<Self::Item>,
     {
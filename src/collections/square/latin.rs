@@ -0,0 +1,164 @@
+//! Random Latin squares
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::collections::square::{Square, SquareValue};
+
+/// Generates a random Latin square with the values `0..width`.
+///
+/// This uses the Markov chain of Jacobson and Matthews, starting from a cyclic Latin square. Every
+/// Latin square of the given width may be produced, and the distribution is approximately uniform.
+pub fn random_latin_square(width: SquareValue, rng: &mut impl Rng) -> Square<SquareValue> {
+    let mut cube = IncidenceCube::cyclic(width as usize);
+    if width > 1 {
+        let steps = (width as usize).pow(3);
+        for _ in 0..steps {
+            cube.step(rng);
+        }
+        // only a proper cube is a Latin square
+        while cube.improper.is_some() {
+            cube.step(rng);
+        }
+    }
+    cube.to_square()
+}
+
+/// A Latin square represented as a cube of rows, columns and symbols, where a cell is 1 if the
+/// symbol is at the row and column and 0 otherwise. Every line of the cube sums to 1.
+///
+/// While the Markov chain is moving, the cube may be "improper" with a single cell of -1.
+struct IncidenceCube {
+    width: usize,
+    cells: Vec<i8>,
+    /// The cell with a value of -1, if any
+    improper: Option<(usize, usize, usize)>,
+}
+
+impl IncidenceCube {
+    fn cyclic(width: usize) -> Self {
+        let mut cube = Self {
+            width,
+            cells: vec![0; width.pow(3)],
+            improper: None,
+        };
+        for row in 0..width {
+            for col in 0..width {
+                cube.add(row, col, (row + col) % width, 1);
+            }
+        }
+        cube
+    }
+
+    fn get(&self, row: usize, col: usize, symbol: usize) -> i8 {
+        self.cells[(row * self.width + col) * self.width + symbol]
+    }
+
+    fn add(&mut self, row: usize, col: usize, symbol: usize, value: i8) {
+        self.cells[(row * self.width + col) * self.width + symbol] += value;
+    }
+
+    /// Moves the Markov chain by one step
+    fn step(&mut self, rng: &mut impl Rng) {
+        let (row, col, symbol) = match self.improper {
+            Some(cell) => cell,
+            None => loop {
+                let row = rng.gen_range(0..self.width);
+                let col = rng.gen_range(0..self.width);
+                let symbol = rng.gen_range(0..self.width);
+                if self.get(row, col, symbol) == 0 {
+                    break (row, col, symbol);
+                }
+            },
+        };
+        // each line through an improper cell has two cells of 1 to choose from
+        let row2 = self.choose_one(rng, |i| self.get(i, col, symbol));
+        let col2 = self.choose_one(rng, |i| self.get(row, i, symbol));
+        let symbol2 = self.choose_one(rng, |i| self.get(row, col, i));
+
+        self.add(row, col, symbol, 1);
+        self.add(row, col2, symbol2, 1);
+        self.add(row2, col, symbol2, 1);
+        self.add(row2, col2, symbol, 1);
+
+        self.add(row, col, symbol2, -1);
+        self.add(row, col2, symbol, -1);
+        self.add(row2, col, symbol, -1);
+        self.add(row2, col2, symbol2, -1);
+
+        self.improper = if self.get(row2, col2, symbol2) < 0 {
+            Some((row2, col2, symbol2))
+        } else {
+            None
+        };
+    }
+
+    /// Randomly chooses a position in a line of the cube where the value is 1
+    fn choose_one(&self, rng: &mut impl Rng, line: impl Fn(usize) -> i8) -> usize {
+        let positions: Vec<usize> = (0..self.width).filter(|&i| line(i) == 1).collect();
+        *positions.choose(rng).unwrap()
+    }
+
+    fn to_square(&self) -> Square<SquareValue> {
+        debug_assert!(self.improper.is_none());
+        let elements = (0..self.width)
+            .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                (0..self.width)
+                    .find(|&symbol| self.get(row, col, symbol) == 1)
+                    .unwrap() as SquareValue
+            });
+        Square::from_iter(elements).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::collections::square::{random_latin_square, IsSquare, Square, SquareValue};
+    use crate::{HashMap, HashSet};
+
+    fn is_latin_square(square: &Square<SquareValue>) -> bool {
+        square.vectors().all(|vector| {
+            let mut values: Vec<_> = square.vector(vector).iter().copied().collect();
+            values.sort_unstable();
+            values.into_iter().eq(0..square.width())
+        })
+    }
+
+    #[test]
+    fn latin_square() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for width in 1..=12 {
+            let square = random_latin_square(width, &mut rng);
+            assert_eq!(width, square.width());
+            assert!(is_latin_square(&square));
+        }
+    }
+
+    #[test]
+    fn uniform() {
+        // there are 12 Latin squares of width 3
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut counts: HashMap<Vec<SquareValue>, u32> = HashMap::default();
+        for _ in 0..1200 {
+            *counts
+                .entry(random_latin_square(3, &mut rng).to_vec())
+                .or_default() += 1;
+        }
+        assert_eq!(12, counts.len());
+        assert!(counts.values().all(|&count| (60..=140).contains(&count)));
+    }
+
+    #[test]
+    fn not_only_cyclic() {
+        // there are 576 Latin squares of width 4, but only 144 are cyclic
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let squares: HashSet<Vec<SquareValue>> = (0..2000)
+            .map(|_| random_latin_square(4, &mut rng).to_vec())
+            .collect();
+        assert!(squares.len() > 144);
+    }
+}
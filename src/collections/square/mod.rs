@@ -6,9 +6,11 @@ use std::iter::{Chain, Map, StepBy};
 use std::ops::{Deref, Index, IndexMut, Range};
 
 pub(crate) use self::coord::Coord;
+pub use self::latin::random_latin_square;
 pub(crate) use self::vector::{Dimension, Vector};
 
 mod coord;
+mod latin;
 mod vector;
 
 type VectorsInner = Map<Range<SquareValue>, fn(SquareValue) -> Vector>;
@@ -86,10 +88,7 @@ pub(crate) struct SquareCellRef<'a, S: IsSquare> {
 // Clone and Copy cannot be derived - see https://github.com/rust-lang/rust/issues/26925
 impl<S: IsSquare> Clone for SquareCellRef<'_, S> {
     fn clone(&self) -> Self {
-        Self {
-            square: self.square,
-            index: self.index,
-        }
+        *self
     }
 }
 
@@ -199,10 +198,7 @@ pub(crate) struct SquareVector<'a, T> {
 // Clone and Copy cannot be derived - see https://github.com/rust-lang/rust/issues/26925
 impl<'a, T> Clone for SquareVector<'a, T> {
     fn clone(&self) -> Self {
-        Self {
-            square: self.square,
-            vector: self.vector,
-        }
+        *self
     }
 }
 
@@ -293,6 +289,7 @@ impl<T> Square<T> {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_iter(iter: impl IntoIterator<Item = T>) -> Result<Self, NonSquareLength> {
         iter.into_iter().collect::<Vec<T>>().try_into()
     }
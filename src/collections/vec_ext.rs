@@ -1,3 +1,4 @@
+#[allow(dead_code)]
 pub(crate) trait VecExt<T> {
     /// Appends a default element to the back of a collection
     /// and returns a mutable reference to the value.
@@ -15,6 +15,105 @@ impl InvalidPuzzle {
     }
 }
 
+#[derive(Error, Debug)]
+#[error("the grid does not fit the puzzle: {}", msg)]
+pub struct InvalidGrid {
+    msg: String,
+}
+
+impl InvalidGrid {
+    pub(crate) fn new(msg: String) -> Self {
+        Self { msg }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("invalid generator config: {}", msg)]
+pub struct InvalidGeneratorConfig {
+    msg: String,
+}
+
+impl InvalidGeneratorConfig {
+    pub(crate) fn new(msg: String) -> Self {
+        Self { msg }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("invalid difficulty tier: \"{}\"", value)]
+pub struct ParseDifficultyTierError {
+    value: String,
+}
+
+impl ParseDifficultyTierError {
+    pub(crate) fn new(value: String) -> Self {
+        Self { value }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("invalid grid: {}", msg)]
+pub struct ParsePlayerGridError {
+    msg: String,
+}
+
+impl ParsePlayerGridError {
+    pub(crate) fn new(msg: String) -> Self {
+        Self { msg }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("invalid theme on line {}: {}", line, msg)]
+pub struct ParseThemeError {
+    line: usize,
+    msg: String,
+}
+
+impl ParseThemeError {
+    pub(crate) fn new(line: usize, msg: String) -> Self {
+        Self { line, msg }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ThemeFromFileError {
+    #[error("error reading theme file")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Parse(#[from] ParseThemeError),
+}
+
+#[derive(Error, Debug)]
+#[error("the puzzle has no solution with these values")]
+pub struct Contradiction;
+
+#[derive(Error, Debug)]
+pub enum HintError {
+    #[error("the grid does not fit the puzzle: {}", msg)]
+    InvalidGrid { msg: String },
+    #[error(transparent)]
+    Contradiction(#[from] Contradiction),
+}
+
+#[derive(Error, Debug)]
+pub enum SolverStateError {
+    #[error("the cell or value does not fit the puzzle: {}", msg)]
+    InvalidInput { msg: String },
+    #[error(transparent)]
+    Contradiction(#[from] Contradiction),
+}
+
+#[derive(Error, Debug)]
+pub enum SaveImageError {
+    #[error("error writing image file")]
+    Io(#[from] io::Error),
+    #[error("error drawing image: {}", msg)]
+    Render { msg: String },
+    #[error("invalid booklet layout: {}", msg)]
+    InvalidLayout { msg: String },
+}
+
 #[derive(Error, Debug)]
 pub enum PuzzleFromFileError {
     #[error("error reading puzzle file")]
@@ -23,6 +122,27 @@ pub enum PuzzleFromFileError {
     Parse(#[from] ParsePuzzleError),
     #[error(transparent)]
     InvalidPuzzle(#[from] InvalidPuzzle),
+    #[cfg(feature = "json")]
+    #[error("invalid puzzle JSON")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ReadCollectionError {
+    #[error("error reading puzzle collection")]
+    Io(#[from] io::Error),
+    /// `index` counts puzzles from 1
+    #[error("error reading puzzle {index} of the collection")]
+    Parse {
+        index: usize,
+        source: ParsePuzzleError,
+    },
+    #[cfg(feature = "json")]
+    #[error("error reading puzzle {index} of the collection")]
+    Json {
+        index: usize,
+        source: serde_json::Error,
+    },
 }
 
 pub const UNEXPECTED_END: ParseError = ParseError::from_type(ParsePuzzleErrorType::UnexpectedEnd);
@@ -0,0 +1,287 @@
+//! Options for generating puzzles
+
+use crate::error::InvalidGeneratorConfig;
+use crate::puzzle::Operator;
+
+const DEFAULT_MAX_CAGE_SIZE: usize = 4;
+const DEFAULT_AVERAGE_CAGE_SIZE: f32 = 2.2;
+const DEFAULT_CAGE_SIZE_DISTRIBUTION: f32 = 0.5;
+
+/// Options for generating puzzles
+#[derive(Clone, Debug)]
+pub struct GeneratorConfig {
+    /// The maximum number of cells in a cage
+    max_cage_size: usize,
+    /// Cages are merged until the average cage size reaches this value
+    average_cage_size: f32,
+    /// The portion of remaining cage borders that are considered for each cage size
+    cage_size_distribution: f32,
+    /// The maximum number of cages with a single cell
+    max_single_cell_cages: Option<usize>,
+    /// Operators that may be used for cages with multiple cells with relative weights.
+    /// Every weight is greater than zero.
+    operator_weights: Vec<(Operator, u32)>,
+    /// Hide the operators of cages with multiple cells
+    hide_operators: bool,
+    /// Seed of the first generated puzzle
+    seed: Option<u64>,
+}
+
+impl GeneratorConfig {
+    /// The maximum number of cells in a cage
+    pub fn max_cage_size(&self) -> usize {
+        self.max_cage_size
+    }
+
+    /// Cages are merged until the average cage size reaches this value
+    pub fn average_cage_size(&self) -> f32 {
+        self.average_cage_size
+    }
+
+    /// The portion of remaining cage borders that are considered for each cage size, starting
+    /// with the smallest cages. A higher value produces more small cages.
+    pub fn cage_size_distribution(&self) -> f32 {
+        self.cage_size_distribution
+    }
+
+    /// The maximum number of cages with a single cell, if limited
+    pub fn max_single_cell_cages(&self) -> Option<usize> {
+        self.max_single_cell_cages
+    }
+
+    /// The operators that may be used for cages with multiple cells
+    pub fn operators(&self) -> impl Iterator<Item = Operator> + '_ {
+        self.operator_weights.iter().map(|&(operator, _)| operator)
+    }
+
+    /// The relative weight of choosing an operator for a cage, or 0 if the operator is not used
+    pub fn operator_weight(&self, operator: Operator) -> u32 {
+        self.operator_weights
+            .iter()
+            .find(|&&(o, _)| o == operator)
+            .map_or(0, |&(_, weight)| weight)
+    }
+
+    /// If true, cages with multiple cells do not show an operator
+    pub fn hide_operators(&self) -> bool {
+        self.hide_operators
+    }
+
+    /// The seed of the first generated puzzle, if any
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfigBuilder::new().build().unwrap()
+    }
+}
+
+/// Creates a `GeneratorConfig`
+#[derive(Clone, Debug)]
+pub struct GeneratorConfigBuilder {
+    max_cage_size: Option<usize>,
+    average_cage_size: f32,
+    cage_size_distribution: f32,
+    max_single_cell_cages: Option<usize>,
+    operator_weights: Vec<(Operator, u32)>,
+    hide_operators: bool,
+    seed: Option<u64>,
+}
+
+impl GeneratorConfigBuilder {
+    pub fn new() -> Self {
+        Self {
+            max_cage_size: None,
+            average_cage_size: DEFAULT_AVERAGE_CAGE_SIZE,
+            cage_size_distribution: DEFAULT_CAGE_SIZE_DISTRIBUTION,
+            max_single_cell_cages: None,
+            operator_weights: Operator::HIDDEN_OPERATORS
+                .iter()
+                .map(|&operator| (operator, 1))
+                .collect(),
+            hide_operators: false,
+            seed: None,
+        }
+    }
+
+    /// Sets the maximum number of cells in a cage.
+    ///
+    /// If not set, the maximum cage size is 4, or 3 if addition or multiplication is not used.
+    /// Subtraction and division only work with 2 cells, so the maximum cage size must be at most 2
+    /// if neither addition nor multiplication is used.
+    pub fn max_cage_size(&mut self, max_cage_size: usize) -> &mut Self {
+        self.max_cage_size = Some(max_cage_size);
+        self
+    }
+
+    /// Sets the average cage size. Cages are merged until the average cage size reaches this
+    /// value, or until no more cages can be merged.
+    pub fn average_cage_size(&mut self, average_cage_size: f32) -> &mut Self {
+        self.average_cage_size = average_cage_size;
+        self
+    }
+
+    /// Sets the portion of remaining cage borders that are considered for each cage size,
+    /// starting with the smallest cages. Must be greater than 0 and at most 1.
+    pub fn cage_size_distribution(&mut self, cage_size_distribution: f32) -> &mut Self {
+        self.cage_size_distribution = cage_size_distribution;
+        self
+    }
+
+    /// Sets the maximum number of cages with a single cell. Extra single cell cages are merged
+    /// with a neighboring cage when the neighboring cage is smaller than the maximum cage size.
+    pub fn max_single_cell_cages(&mut self, max_single_cell_cages: usize) -> &mut Self {
+        self.max_single_cell_cages = Some(max_single_cell_cages);
+        self
+    }
+
+    /// Sets the operators that may be used for cages with multiple cells, with equal weights
+    pub fn operators(&mut self, operators: &[Operator]) -> &mut Self {
+        self.operator_weights.clear();
+        for &operator in operators {
+            self.operator_weight(operator, 1);
+        }
+        self
+    }
+
+    /// Sets the relative weight of choosing an operator for a cage. An operator with a weight
+    /// of 0 is not used.
+    pub fn operator_weight(&mut self, operator: Operator, weight: u32) -> &mut Self {
+        self.operator_weights.retain(|&(o, _)| o != operator);
+        if weight > 0 {
+            self.operator_weights.push((operator, weight));
+        }
+        self
+    }
+
+    /// Sets whether to hide the operators of cages with multiple cells (a "no-op" puzzle).
+    /// The operators are still used to choose the cage targets.
+    pub fn hide_operators(&mut self, hide_operators: bool) -> &mut Self {
+        self.hide_operators = hide_operators;
+        self
+    }
+
+    /// Sets the seed of the first generated puzzle. Without a seed, puzzles are different each time.
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn build(&self) -> Result<GeneratorConfig, InvalidGeneratorConfig> {
+        if self.operator_weights.is_empty() {
+            return Err(InvalidGeneratorConfig::new(
+                "at least one operator is required".into(),
+            ));
+        }
+        if self
+            .operator_weights
+            .iter()
+            .any(|&(operator, _)| operator == Operator::Nop)
+        {
+            return Err(InvalidGeneratorConfig::new(
+                "cages with multiple cells must have an operator".into(),
+            ));
+        }
+        if self.max_cage_size == Some(0) {
+            return Err(InvalidGeneratorConfig::new(
+                "max cage size must be at least 1".into(),
+            ));
+        }
+        if self.average_cage_size.is_nan() || self.average_cage_size < 1.0 {
+            return Err(InvalidGeneratorConfig::new(
+                "average cage size must be at least 1".into(),
+            ));
+        }
+        if !(self.cage_size_distribution > 0.0 && self.cage_size_distribution <= 1.0) {
+            return Err(InvalidGeneratorConfig::new(
+                "cage size distribution must be greater than 0 and at most 1".into(),
+            ));
+        }
+        let has_operator = |operator| self.operator_weights.iter().any(|&(o, _)| o == operator);
+        let (has_add, has_multiply) = (
+            has_operator(Operator::Add),
+            has_operator(Operator::Multiply),
+        );
+        // Cages are kept small when only addition or multiplication is used since larger cages
+        // are more likely to cause multiple solutions.
+        let max_cage_size = match (has_add, has_multiply) {
+            (false, false) => match self.max_cage_size {
+                Some(max_cage_size) if max_cage_size > 2 => {
+                    return Err(InvalidGeneratorConfig::new(
+                        "max cage size must be at most 2 without addition or multiplication".into(),
+                    ))
+                }
+                max_cage_size => max_cage_size.unwrap_or(2),
+            },
+            (true, true) => self.max_cage_size.unwrap_or(DEFAULT_MAX_CAGE_SIZE),
+            _ => self.max_cage_size.unwrap_or(DEFAULT_MAX_CAGE_SIZE - 1),
+        };
+        Ok(GeneratorConfig {
+            max_cage_size,
+            average_cage_size: self.average_cage_size,
+            cage_size_distribution: self.cage_size_distribution,
+            max_single_cell_cages: self.max_single_cell_cages,
+            operator_weights: self.operator_weights.clone(),
+            hide_operators: self.hide_operators,
+            seed: self.seed,
+        })
+    }
+}
+
+impl Default for GeneratorConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::GeneratorConfigBuilder;
+    use crate::puzzle::Operator;
+
+    #[test]
+    fn operators() {
+        let config = GeneratorConfigBuilder::new()
+            .operators(&[Operator::Add, Operator::Subtract])
+            .operator_weight(Operator::Add, 3)
+            .build()
+            .unwrap();
+        assert_eq!(3, config.operator_weight(Operator::Add));
+        assert_eq!(1, config.operator_weight(Operator::Subtract));
+        assert_eq!(0, config.operator_weight(Operator::Multiply));
+        // multiplication is not used, so cages are kept smaller
+        assert_eq!(3, config.max_cage_size());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(GeneratorConfigBuilder::new()
+            .operators(&[])
+            .build()
+            .is_err());
+        assert!(GeneratorConfigBuilder::new()
+            .operators(&[Operator::Nop])
+            .build()
+            .is_err());
+        assert!(GeneratorConfigBuilder::new()
+            .max_cage_size(0)
+            .build()
+            .is_err());
+        assert!(GeneratorConfigBuilder::new()
+            .average_cage_size(0.5)
+            .build()
+            .is_err());
+        assert!(GeneratorConfigBuilder::new()
+            .cage_size_distribution(0.0)
+            .build()
+            .is_err());
+        assert!(GeneratorConfigBuilder::new()
+            .operators(&[Operator::Subtract, Operator::Divide])
+            .max_cage_size(3)
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,605 @@
+//! Generate random KenKen puzzles
+
+use std::collections::VecDeque;
+use std::mem;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::collections::square::{random_latin_square, Square, SquareValue};
+use crate::puzzle::Puzzle;
+use crate::puzzle::{Cage, CellId, Solution, Value};
+use crate::puzzle::{CageId, Operator};
+use crate::solve::{DifficultyTier, PuzzleSolver, SolveResult};
+
+use self::unique::make_unique;
+
+pub use self::config::{GeneratorConfig, GeneratorConfigBuilder};
+
+mod config;
+mod unique;
+
+type BorderId = u32;
+
+/// The number of puzzles generated to find one with a difficulty tier before giving up
+pub const MAX_DIFFICULTY_ATTEMPTS: u32 = 10_000;
+
+/// A cage of a puzzle being generated. The operator is not hidden yet.
+struct GeneratedCage {
+    cells: Vec<CellId>,
+    operator: Operator,
+}
+
+/// Generates random puzzles.
+///
+/// Each puzzle is generated from its own seed, and the seed of the next puzzle is drawn from the
+/// random number generator of the previous puzzle. So a puzzle can be reproduced by using its seed
+/// as the seed of the generator config. The output is the same for the same seed and config.
+pub struct PuzzleGenerator {
+    config: GeneratorConfig,
+    next_seed: u64,
+    last_seed: Option<u64>,
+}
+
+impl PuzzleGenerator {
+    pub fn new(config: GeneratorConfig) -> Self {
+        let next_seed = config.seed().unwrap_or_else(|| rand::thread_rng().gen());
+        Self {
+            config,
+            next_seed,
+            last_seed: None,
+        }
+    }
+
+    pub fn config(&self) -> &GeneratorConfig {
+        &self.config
+    }
+
+    /// The seed of the most recently generated puzzle
+    pub fn last_seed(&self) -> Option<u64> {
+        self.last_seed
+    }
+
+    /// Generates a random puzzle that has a single solution.
+    ///
+    /// Where the solver finds multiple solutions, cages are repaired locally by changing
+    /// operators, merging cages or splitting cages until the puzzle has a single solution.
+    pub fn generate(&mut self, width: SquareValue) -> Puzzle {
+        let (puzzle, _solution) = self.generate_inner(width, true);
+        puzzle
+    }
+
+    /// Generates a random puzzle which may not have a single solution
+    pub fn generate_untested(&mut self, width: SquareValue) -> Puzzle {
+        let (puzzle, _solution) = self.generate_untested_with_solution(width);
+        puzzle
+    }
+
+    /// Generates puzzles until one has a single solution and the given difficulty tier. Returns
+    /// `None` if none of `MAX_DIFFICULTY_ATTEMPTS` puzzles have the tier, which is likely when the
+    /// width is too small for the tier.
+    pub fn generate_with_difficulty(
+        &mut self,
+        width: SquareValue,
+        tier: DifficultyTier,
+    ) -> Option<Puzzle> {
+        self.generate_with_difficulty_attempts(width, tier, MAX_DIFFICULTY_ATTEMPTS)
+    }
+
+    /// Same as `generate_with_difficulty`, giving up after `max_attempts` puzzles
+    pub(crate) fn generate_with_difficulty_attempts(
+        &mut self,
+        width: SquareValue,
+        tier: DifficultyTier,
+        max_attempts: u32,
+    ) -> Option<Puzzle> {
+        for attempt in 1..=max_attempts {
+            let puzzle = self.generate(width);
+            let result = PuzzleSolver::new(&puzzle).solve_without_steps();
+            if let SolveResult::Solved(data) = result {
+                if data.difficulty.tier() == tier {
+                    debug!(
+                        "Generated a puzzle with difficulty {} after {} attempts",
+                        data.difficulty, attempt
+                    );
+                    return Some(puzzle);
+                }
+            }
+        }
+        None
+    }
+
+    /// Generates a random puzzle and a solution to the puzzle.
+    // Note: The solution returned is not very important since the puzzle might have multiple solutions
+    pub fn generate_untested_with_solution(&mut self, width: SquareValue) -> (Puzzle, Solution) {
+        self.generate_inner(width, false)
+    }
+
+    fn generate_inner(&mut self, width: SquareValue, unique: bool) -> (Puzzle, Solution) {
+        let seed = self.next_seed;
+        // ChaCha8Rng is used since its output is stable across versions, unlike StdRng
+        let rng = &mut ChaCha8Rng::seed_from_u64(seed);
+        let config = &self.config;
+        let solution = random_latin_square(width, rng).map(|&value| value as Value + 1);
+        debug!("Solution:\n{}", &solution);
+        let cage_cells = generate_cage_cells(width, config, rng);
+        let mut cages = assign_operators(width, cage_cells, &solution, config, rng);
+        let puzzle = if unique {
+            make_unique(width, &solution, &mut cages, config, rng)
+        } else {
+            build_puzzle(width, &solution, &cages, config)
+        };
+        self.next_seed = rng.gen();
+        self.last_seed = Some(seed);
+        (puzzle, solution)
+    }
+}
+
+/// Creates cages with random operators. Cells of a cage that does not work with any of the
+/// operators are merged into neighboring cages where possible, and single cell cages are merged
+/// until there are at most `max_single_cell_cages`.
+fn assign_operators(
+    width: SquareValue,
+    cage_cells: Vec<Vec<CellId>>,
+    solution: &Solution,
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) -> Vec<GeneratedCage> {
+    let mut cages = Vec::with_capacity(cage_cells.len());
+    let mut leftover = Vec::new();
+    for cells in cage_cells {
+        let new = new_cages(cells, solution, config, rng);
+        if new.len() > 1 {
+            leftover.extend(cages.len()..cages.len() + new.len());
+        }
+        cages.extend(new);
+    }
+    let mut cage_map = vec![0; solution.len()];
+    for (cage_id, cage) in cages.iter().enumerate() {
+        for &cell in &cage.cells {
+            cage_map[cell] = cage_id;
+        }
+    }
+    leftover.shuffle(rng);
+    for cage_id in leftover {
+        merge_single_cell_cage(
+            width,
+            &mut cages,
+            &mut cage_map,
+            cage_id,
+            solution,
+            config,
+            rng,
+        );
+    }
+    if let Some(max_count) = config.max_single_cell_cages() {
+        let mut single_cell_cages: Vec<CageId> = (0..cages.len())
+            .filter(|&i| cages[i].cells.len() == 1)
+            .collect();
+        let mut count = single_cell_cages.len();
+        single_cell_cages.shuffle(rng);
+        for cage_id in single_cell_cages {
+            if count <= max_count {
+                break;
+            }
+            let target = merge_single_cell_cage(
+                width,
+                &mut cages,
+                &mut cage_map,
+                cage_id,
+                solution,
+                config,
+                rng,
+            );
+            if let Some(target) = target {
+                if cages[target].cells.len() == 2 {
+                    count -= 1;
+                }
+                count -= 1;
+            }
+        }
+    }
+    cages.retain(|cage| !cage.cells.is_empty());
+    cages
+}
+
+/// Creates a cage from cells with a random operator. If none of the operators work with the
+/// values of the cells, every cell becomes a single cell cage.
+fn new_cages(
+    cells: Vec<CellId>,
+    solution: &Solution,
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) -> Vec<GeneratedCage> {
+    let values = cells.iter().map(|&i| solution[i]).collect::<Vec<_>>();
+    match random_operator(&values, config, rng) {
+        Some(operator) => vec![GeneratedCage { cells, operator }],
+        None => cells
+            .into_iter()
+            .map(|cell| GeneratedCage {
+                cells: vec![cell],
+                operator: Operator::Nop,
+            })
+            .collect(),
+    }
+}
+
+/// Merges a single cell cage into a neighboring cage that is smaller than the maximum cage size
+/// and works with one of the operators, preferring smaller neighbors. Returns the cage that the
+/// cell was merged into, if any.
+#[allow(clippy::too_many_arguments)]
+fn merge_single_cell_cage(
+    width: SquareValue,
+    cages: &mut [GeneratedCage],
+    cage_map: &mut [CageId],
+    cage_id: CageId,
+    solution: &Solution,
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) -> Option<CageId> {
+    if cages[cage_id].cells.len() != 1 {
+        // the cell was merged into another cage or another cell was merged into this cage
+        return None;
+    }
+    let cell = cages[cage_id].cells[0];
+    let mut neighbors: Vec<CageId> = adjacent_cells(width, cell)
+        .map(|i| cage_map[i])
+        .filter(|&i| cages[i].cells.len() < config.max_cage_size())
+        .collect();
+    neighbors.sort_unstable();
+    neighbors.dedup();
+    neighbors.shuffle(rng);
+    neighbors.sort_by_key(|&i| cages[i].cells.len());
+    for target in neighbors {
+        let mut cells = cages[target].cells.clone();
+        cells.push(cell);
+        let values = cells.iter().map(|&i| solution[i]).collect::<Vec<_>>();
+        if let Some(operator) = random_operator(&values, config, rng) {
+            cages[target] = GeneratedCage { cells, operator };
+            cages[cage_id].cells.clear();
+            cage_map[cell] = target;
+            return Some(target);
+        }
+    }
+    None
+}
+
+fn build_puzzle(
+    width: SquareValue,
+    solution: &Solution,
+    cages: &[GeneratedCage],
+    config: &GeneratorConfig,
+) -> Puzzle {
+    let cages = cages
+        .iter()
+        .map(|cage| {
+            let values = cage.cells.iter().map(|&i| solution[i]).collect::<Vec<_>>();
+            let target = find_cage_target(cage.operator, &values);
+            let operator = if config.hide_operators() {
+                Operator::Nop
+            } else {
+                cage.operator
+            };
+            Cage::new(cage.cells.clone(), operator, target).unwrap()
+        })
+        .collect();
+    Puzzle::new(width, cages).unwrap()
+}
+
+fn shuffled_inner_borders(square_width: SquareValue, rng: &mut impl Rng) -> Vec<BorderId> {
+    let num_borders = square_width * (square_width - 1) * 2;
+    let mut borders = (0..num_borders).collect::<Vec<_>>();
+    borders.shuffle(rng);
+    borders
+}
+
+fn cells_touching_border(square_width: SquareValue, border_id: BorderId) -> (CellId, CellId) {
+    let (width, border_id) = (square_width as CellId, border_id as CellId);
+    let a = border_id / 2;
+    let (a, b) = if border_id % 2 == 0 {
+        (a, a + width)
+    } else {
+        let b = width - 1;
+        let c = a / b * width + a % b;
+        (c, c + 1)
+    };
+    (a, b)
+}
+
+fn generate_cage_cells(
+    puzzle_width: SquareValue,
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) -> Vec<Vec<CellId>> {
+    let num_cells = (puzzle_width as usize).pow(2);
+    let mut cage_map = Square::<CageId>::from_iter(0..num_cells).unwrap();
+    let mut cages: Vec<Vec<CellId>> = (0..num_cells).map(|i| vec![i]).collect();
+    let min_cage_count = (num_cells as f32 / config.average_cage_size()) as usize;
+    let mut borders = VecDeque::from(shuffled_inner_borders(puzzle_width, rng));
+    'target_cage_sizes: for target_cage_size in 2..=config.max_cage_size() {
+        let border_count = (borders.len() as f32 * config.cage_size_distribution()) as usize;
+        for _ in 0..border_count {
+            let border_id = borders.pop_front().unwrap();
+            let (cell1, cell2) = cells_touching_border(puzzle_width, border_id);
+            let (mut cage_a, mut cage_b) = (cage_map[cell1], cage_map[cell2]);
+            if cage_a > cage_b {
+                mem::swap(&mut cage_a, &mut cage_b)
+            }
+            let cage_size = cages[cage_a].len() + cages[cage_b].len();
+            if cage_size != target_cage_size {
+                if cage_size > target_cage_size {
+                    borders.push_back(border_id);
+                }
+                continue;
+            }
+            let a = cages.pop().unwrap();
+            if cage_b == cages.len() {
+                for &i in &a {
+                    cage_map[i] = cage_a
+                }
+                cages[cage_a].extend(a);
+            } else {
+                for &i in &a {
+                    cage_map[i] = cage_b
+                }
+                let b = mem::replace(&mut cages[cage_b], a);
+                for &i in &b {
+                    cage_map[i] = cage_a
+                }
+                cages[cage_a].extend(b);
+            }
+            if cages.len() == min_cage_count {
+                break 'target_cage_sizes;
+            }
+        }
+    }
+    cages
+}
+
+/// Cells that share a border with a cell
+fn adjacent_cells(square_width: SquareValue, cell: CellId) -> impl Iterator<Item = CellId> {
+    let width = square_width as CellId;
+    let (row, col) = (cell / width, cell % width);
+    [
+        (row > 0).then(|| cell - width),
+        (row + 1 < width).then(|| cell + width),
+        (col > 0).then(|| cell - 1),
+        (col + 1 < width).then(|| cell + 1),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Chooses a random operator that works with the values of a cage, using the operator weights
+fn random_operator(
+    values: &[i32],
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) -> Option<Operator> {
+    if values.len() == 1 {
+        return Some(Operator::Nop);
+    }
+    let mut operators = possible_operators(values);
+    operators.retain(|&operator| config.operator_weight(operator) > 0);
+    operators
+        .choose_weighted(rng, |&operator| config.operator_weight(operator))
+        .ok()
+        .copied()
+}
+
+fn possible_operators(values: &[i32]) -> Vec<Operator> {
+    if values.len() < 2 {
+        panic!("multiple values must be provided")
+    }
+    let mut operators = vec![Operator::Add, Operator::Multiply];
+    if values.len() == 2 {
+        operators.push(Operator::Subtract);
+        let (min, max) = min_max(values);
+        if max % min == 0 {
+            operators.push(Operator::Divide);
+        }
+    }
+    operators
+}
+
+fn find_cage_target(operator: Operator, values: &[Value]) -> Value {
+    match operator {
+        Operator::Add => values.iter().sum(),
+        Operator::Subtract => {
+            let (min, max) = min_max(values);
+            max - min
+        }
+        Operator::Multiply => values.iter().product(),
+        Operator::Divide => {
+            let (min, max) = min_max(values);
+            max / min
+        }
+        Operator::Nop => values[0],
+    }
+}
+
+fn min_max<T>(slice: &[T]) -> (T, T)
+where
+    T: Copy + PartialOrd,
+{
+    let mut min = slice[0];
+    let mut max = slice[0];
+    for &e in &slice[1..] {
+        if e < min {
+            min = e
+        }
+        if e > max {
+            max = e
+        }
+    }
+    (min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collections::square::IsSquare;
+    use crate::generate::{
+        adjacent_cells, GeneratorConfig, GeneratorConfigBuilder, PuzzleGenerator,
+    };
+    use crate::puzzle::Operator;
+    use crate::solve::{DifficultyTier, PuzzleSolver};
+
+    #[test]
+    fn test() {
+        let mut generator = PuzzleGenerator::new(GeneratorConfig::default());
+        for size in 3..=10 {
+            for _ in 0..10 {
+                // just test that it does not panic (such as from an invalid puzzle)
+                let _ = generator.generate_untested(size);
+            }
+        }
+    }
+
+    #[test]
+    fn unique() {
+        for config in [
+            GeneratorConfig::default(),
+            GeneratorConfigBuilder::new()
+                .hide_operators(true)
+                .build()
+                .unwrap(),
+        ] {
+            let mut generator = PuzzleGenerator::new(config);
+            for size in 3..=9 {
+                let (puzzle, solution) = generator.generate_inner(size, true);
+                let result = PuzzleSolver::new(&puzzle).solve().unwrap();
+                assert_eq!(&solution, &result.solved().unwrap().solution);
+            }
+        }
+    }
+
+    #[test]
+    fn with_difficulty() {
+        let config = GeneratorConfigBuilder::new().seed(1).build().unwrap();
+        let mut generator = PuzzleGenerator::new(config);
+        for tier in [DifficultyTier::Easy, DifficultyTier::Expert] {
+            let puzzle = generator.generate_with_difficulty(4, tier).unwrap();
+            let result = PuzzleSolver::new(&puzzle).solve().unwrap();
+            assert_eq!(tier, result.solved().unwrap().difficulty.tier());
+        }
+        // every 2x2 puzzle is easy
+        assert_eq!(
+            None,
+            generator.generate_with_difficulty_attempts(2, DifficultyTier::Expert, 50)
+        );
+    }
+
+    #[test]
+    fn operators() {
+        for operators in [
+            &[Operator::Add][..],
+            &[Operator::Multiply],
+            &[Operator::Subtract, Operator::Divide],
+            &[Operator::Divide],
+        ] {
+            let config = GeneratorConfigBuilder::new()
+                .operators(operators)
+                .build()
+                .unwrap();
+            let mut generator = PuzzleGenerator::new(config);
+            for size in 3..=8 {
+                let puzzle = generator.generate_untested(size);
+                assert!(puzzle
+                    .cages()
+                    .all(|cage| cage.operator() == Operator::Nop
+                        || operators.contains(&cage.operator())));
+            }
+        }
+    }
+
+    #[test]
+    fn restricted_operators_rebalance() {
+        let config = GeneratorConfigBuilder::new()
+            .operators(&[Operator::Divide])
+            .max_single_cell_cages(0)
+            .build()
+            .unwrap();
+        let mut generator = PuzzleGenerator::new(config);
+        for size in 3..=8 {
+            let (puzzle, solution) = generator.generate_untested_with_solution(size);
+            // leftover cells are paired up wherever division works
+            for cage in puzzle.cages().filter(|cage| cage.cell_count() == 1) {
+                let cell = cage.cell_ids()[0];
+                for other in adjacent_cells(size, cell) {
+                    if puzzle.cell(other).cage().cell_count() == 1 {
+                        let (a, b) = (solution[cell], solution[other]);
+                        assert_ne!(0, a.max(b) % a.min(b));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hide_operators() {
+        let config = GeneratorConfigBuilder::new()
+            .hide_operators(true)
+            .build()
+            .unwrap();
+        let mut generator = PuzzleGenerator::new(config);
+        for size in 3..=8 {
+            let (puzzle, solution) = generator.generate_untested_with_solution(size);
+            assert!(puzzle.cages().all(|cage| cage.operator() == Operator::Nop));
+            assert!(puzzle.verify_solution(&solution));
+        }
+    }
+
+    #[test]
+    fn seed() {
+        let config = GeneratorConfigBuilder::new().seed(42).build().unwrap();
+        let generate = |config: &GeneratorConfig| {
+            let mut generator = PuzzleGenerator::new(config.clone());
+            let puzzles: Vec<_> = (3..=7).map(|w| generator.generate_untested(w)).collect();
+            let hard = generator
+                .generate_with_difficulty(5, DifficultyTier::Hard)
+                .unwrap();
+            (puzzles, hard, generator.last_seed().unwrap())
+        };
+        let (puzzles, hard, hard_seed) = generate(&config);
+        let (puzzles2, hard2, _) = generate(&config);
+        assert_eq!(puzzles, puzzles2);
+        assert_eq!(hard, hard2);
+
+        // the seed of a puzzle reproduces the puzzle
+        let config = GeneratorConfigBuilder::new()
+            .seed(hard_seed)
+            .build()
+            .unwrap();
+        let mut generator = PuzzleGenerator::new(config);
+        assert_eq!(hard, generator.generate(5));
+        assert_eq!(Some(hard_seed), generator.last_seed());
+    }
+
+    #[test]
+    fn cage_sizes() {
+        let config = GeneratorConfigBuilder::new()
+            .max_cage_size(3)
+            .average_cage_size(2.5)
+            .max_single_cell_cages(1)
+            .build()
+            .unwrap();
+        let mut generator = PuzzleGenerator::new(config);
+        for size in 4..=8 {
+            let puzzle = generator.generate_untested(size);
+            assert!(puzzle.cages().all(|cage| cage.cell_count() <= 3));
+            let single_cell_cages: Vec<_> = puzzle
+                .cages()
+                .filter(|cage| cage.cell_count() == 1)
+                .collect();
+            // extra single cell cages remain only when every neighboring cage is full
+            assert!(
+                single_cell_cages.len() <= 1
+                    || single_cell_cages.iter().all(|cage| {
+                        adjacent_cells(size, cage.cell_ids()[0])
+                            .all(|cell| puzzle.cell(cell).cage().cell_count() == 3)
+                    })
+            );
+        }
+    }
+}
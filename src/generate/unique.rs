@@ -0,0 +1,215 @@
+//! Repair generated puzzles until they have a single solution
+
+use std::collections::VecDeque;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::collections::square::SquareValue;
+use crate::generate::{
+    adjacent_cells, build_puzzle, new_cages, possible_operators, random_operator, GeneratedCage,
+    GeneratorConfig,
+};
+use crate::puzzle::{CageId, CellId, Puzzle, Solution};
+use crate::solve::{PuzzleSolver, SolveResult};
+
+/// Repairs the cages of a puzzle until the puzzle has a single solution, and returns the puzzle.
+///
+/// When the solver finds two solutions, one of the cages with cells that differ between the
+/// solutions is repaired. A repair changes the operator of the cage, merges the cage with a
+/// neighboring cage, or splits the cage. After a number of repairs, cages are only split. This
+/// always ends since a puzzle with only single cell cages has a single solution.
+pub(super) fn make_unique(
+    width: SquareValue,
+    solution: &Solution,
+    cages: &mut Vec<GeneratedCage>,
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) -> Puzzle {
+    let max_other_repairs = width as usize * 2;
+    let mut other_repairs = 0;
+    loop {
+        let puzzle = build_puzzle(width, solution, cages, config);
+        let data = match PuzzleSolver::new(&puzzle).solve_without_steps() {
+            SolveResult::Solved(_) => return puzzle,
+            SolveResult::MultipleSolutions(data) => data,
+            SolveResult::Unsolvable => unreachable!("a generated puzzle has a solution"),
+        };
+        let cage_map = cage_map(width, cages);
+        // single cell cages never differ between solutions
+        let mut ambiguous_cages: Vec<CageId> = data
+            .differing_cells
+            .iter()
+            .map(|&cell| cage_map[cell])
+            .collect();
+        ambiguous_cages.sort_unstable();
+        ambiguous_cages.dedup();
+        let cage_id = *ambiguous_cages.choose(rng).unwrap();
+        debug!(
+            "Repairing ambiguous cage with cells {:?}",
+            cages[cage_id].cells
+        );
+        let repaired = other_repairs < max_other_repairs
+            && match rng.gen_range(0..3) {
+                0 => change_operator(&mut cages[cage_id], solution, config, rng),
+                1 => merge_cage(
+                    width,
+                    cages,
+                    &cage_map,
+                    cage_id,
+                    &ambiguous_cages,
+                    solution,
+                    config,
+                    rng,
+                ),
+                _ => false,
+            };
+        if repaired {
+            other_repairs += 1;
+        } else {
+            split_cage(width, cages, cage_id, solution, config, rng);
+        }
+    }
+}
+
+/// The cage ID of every cell
+fn cage_map(width: SquareValue, cages: &[GeneratedCage]) -> Vec<CageId> {
+    let mut cage_map = vec![0; (width as usize).pow(2)];
+    for (cage_id, cage) in cages.iter().enumerate() {
+        for &cell in &cage.cells {
+            cage_map[cell] = cage_id;
+        }
+    }
+    cage_map
+}
+
+/// Changes the operator of a cage to a different operator that works with the cage's values
+fn change_operator(
+    cage: &mut GeneratedCage,
+    solution: &Solution,
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) -> bool {
+    let values = cage.cells.iter().map(|&i| solution[i]).collect::<Vec<_>>();
+    let mut operators = possible_operators(&values);
+    operators.retain(|&operator| operator != cage.operator && config.operator_weight(operator) > 0);
+    match operators.choose_weighted(rng, |&operator| config.operator_weight(operator)) {
+        Ok(&operator) => {
+            cage.operator = operator;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Merges a cage with a neighboring cage, preferring neighbors that are also ambiguous
+#[allow(clippy::too_many_arguments)]
+fn merge_cage(
+    width: SquareValue,
+    cages: &mut Vec<GeneratedCage>,
+    cage_map: &[CageId],
+    cage_id: CageId,
+    ambiguous_cages: &[CageId],
+    solution: &Solution,
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) -> bool {
+    let mut neighbors: Vec<CageId> = cages[cage_id]
+        .cells
+        .iter()
+        .flat_map(|&cell| adjacent_cells(width, cell))
+        .map(|cell| cage_map[cell])
+        .filter(|&id| id != cage_id)
+        .filter(|&id| cages[cage_id].cells.len() + cages[id].cells.len() <= config.max_cage_size())
+        .collect();
+    neighbors.sort_unstable();
+    neighbors.dedup();
+    neighbors.shuffle(rng);
+    neighbors.sort_by_key(|id| !ambiguous_cages.contains(id));
+    let neighbor = match neighbors.first() {
+        None => return false,
+        Some(&neighbor) => neighbor,
+    };
+    let cells: Vec<CellId> = cages[cage_id]
+        .cells
+        .iter()
+        .chain(&cages[neighbor].cells)
+        .copied()
+        .collect();
+    let values = cells.iter().map(|&i| solution[i]).collect::<Vec<_>>();
+    let operator = match random_operator(&values, config, rng) {
+        None => return false,
+        Some(operator) => operator,
+    };
+    cages[cage_id] = GeneratedCage { cells, operator };
+    cages.swap_remove(neighbor);
+    true
+}
+
+/// Splits a cage into two or more connected cages
+fn split_cage(
+    width: SquareValue,
+    cages: &mut Vec<GeneratedCage>,
+    cage_id: CageId,
+    solution: &Solution,
+    config: &GeneratorConfig,
+    rng: &mut impl Rng,
+) {
+    let cage = cages.swap_remove(cage_id);
+    debug_assert!(cage.cells.len() > 1);
+    let start = *cage.cells.choose(rng).unwrap();
+    let size = rng.gen_range(1..cage.cells.len());
+    let (part, rest): (Vec<_>, Vec<_>) = {
+        let part = connected_cells(width, start, &cage.cells, size);
+        cage.cells.iter().partition(|cell| part.contains(cell))
+    };
+    let mut parts = vec![part];
+    parts.extend(connected_components(width, rest));
+    for cells in parts {
+        cages.extend(new_cages(cells, solution, config, rng));
+    }
+}
+
+/// Up to `max` cells in `cells` that are connected to `start`, found by breadth-first search
+fn connected_cells(width: SquareValue, start: CellId, cells: &[CellId], max: usize) -> Vec<CellId> {
+    let mut found = vec![start];
+    let mut queue = VecDeque::from([start]);
+    while let Some(cell) = queue.pop_front() {
+        for neighbor in adjacent_cells(width, cell) {
+            if found.len() == max {
+                return found;
+            }
+            if cells.contains(&neighbor) && !found.contains(&neighbor) {
+                found.push(neighbor);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    found
+}
+
+/// Splits cells into groups of connected cells
+fn connected_components(width: SquareValue, mut cells: Vec<CellId>) -> Vec<Vec<CellId>> {
+    let mut components = Vec::new();
+    while let Some(&start) = cells.first() {
+        let component = connected_cells(width, start, &cells, cells.len());
+        cells.retain(|cell| !component.contains(cell));
+        components.push(component);
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::unique::connected_components;
+
+    #[test]
+    fn components() {
+        // 0 1 2
+        // 3 4 5
+        // 6 7 8
+        let mut components = connected_components(3, vec![0, 1, 2, 6, 8, 5]);
+        components.iter_mut().for_each(|c| c.sort_unstable());
+        assert_eq!(vec![vec![0, 1, 2, 5, 8], vec![6]], components);
+    }
+}
@@ -3,58 +3,58 @@
 use std::borrow::Cow;
 use std::fmt::{Result, Write};
 use std::fs::File;
-use std::io;
 use std::io::{BufWriter, Write as ioWrite};
 use std::path::Path;
+use std::{fs, io, result};
 
 use itertools::Itertools;
 use once_cell::sync::Lazy;
+use resvg::usvg::{fontdb, TreeParsing, TreePostProc};
+use resvg::{tiny_skia, usvg};
 use xml::Xml;
 
 use crate::collections::square::{Coord, IsSquare, Square, SquareIndex, SquareValue};
+use crate::error::SaveImageError;
 use crate::image::xml::XmlProducer;
-use crate::puzzle::{CellId, Puzzle, Solution};
+use crate::puzzle::{CellId, Conflict, PlayerGrid, Puzzle, Solution};
 use crate::solve::markup::{CellChange, CellChanges};
 use crate::solve::CellVariable;
 use crate::solve::ValueSet;
 use crate::{HashMap, HashSet};
 
+pub use self::animation::GifWriter;
+pub use self::booklet::{BookletBuilder, PageSize};
+pub use self::theme::ImageTheme;
+
 #[macro_use]
 mod xml;
 
-// colors
-const COLOR_CAGE_BORDER: &str = "black";
-const COLOR_CELL_BORDER: &str = "#CCC";
-const COLOR_HIGHLIGHT: &str = "#FFC";
-const COLOR_DOMAIN: &str = "#444";
-const COLOR_DOMAIN_SLASH: &str = "red";
+mod animation;
+mod booklet;
+mod theme;
+
+// the resolution of one unit of an SVG image
+const SVG_DPI: f32 = 96.0;
 
 // dimensions
-const CELL_WIDTH: i32 = 100;
-const BORDER_WIDTH_CELL: i32 = 2;
-const BORDER_WIDTH_CAGE: i32 = 4;
-const BORDER_WIDTH_OUTER: i32 = 6;
-const OUTER_PAD: i32 = BORDER_WIDTH_OUTER - BORDER_WIDTH_CELL / 2;
-const CAGE_SPEC_PAD: i32 = BORDER_WIDTH_CELL + CELL_WIDTH / 16;
 const DOMAIN_SLASH_WIDTH: &str = "1.4";
-const DOMAIN_PAD: i32 = 5;
-const DOMAIN_DX: i32 = 15;
 const MAX_DOMAIN_LINE_LEN: i32 = 5;
 
-// font sizes
-const FONT_SIZE_SOLUTION: i32 = 64;
-const FONT_SIZE_CAGE_SPEC: i32 = 24;
-const FONT_SIZE_DOMAIN: i32 = 20;
+pub(crate) static DEFAULT_THEME: Lazy<ImageTheme> = Lazy::new(ImageTheme::default);
 
-static STYLE: Lazy<String> = Lazy::new(|| {
+fn style(theme: &ImageTheme) -> String {
     format!(
         "\
         text{{\
-          font-family:sans-serif\
+          font-family:{font_family};\
+          fill:{color_text}\
         }}\
         .highlight{{\
           fill:{color_highlight}\
         }}\
+        .conflict{{\
+          fill:{color_conflict}\
+        }}\
         .cage-spec{{\
           font-size:{cage_spec_font_size}px\
         }}\
@@ -63,18 +63,48 @@ static STYLE: Lazy<String> = Lazy::new(|| {
           text-anchor:middle\
         }}\
         .new-solution{{\
-          fill:green\
+          fill:{color_new_solution}\
         }}\
         .domain{{\
           font-size:{domain_font_size}px;\
           fill:{color_domain}\
         }}",
-        cage_spec_font_size = FONT_SIZE_CAGE_SPEC,
-        domain_font_size = FONT_SIZE_DOMAIN,
-        solution_font_size = FONT_SIZE_SOLUTION,
-        color_domain = COLOR_DOMAIN,
-        color_highlight = COLOR_HIGHLIGHT,
+        font_family = theme.font_family,
+        cage_spec_font_size = theme.cage_spec_font_size,
+        domain_font_size = theme.domain_font_size,
+        solution_font_size = theme.solution_font_size,
+        color_text = theme.text,
+        color_new_solution = theme.new_solution,
+        color_domain = theme.domain,
+        color_highlight = theme.highlight,
+        color_conflict = theme.conflict,
     )
+}
+
+/// System fonts used to draw text in PNG images and PDF booklets
+static FONTS: Lazy<fontdb::Database> = Lazy::new(|| {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    let query = fontdb::Query {
+        families: &[fontdb::Family::SansSerif],
+        ..fontdb::Query::default()
+    };
+    if fonts.query(&query).is_none() {
+        // the default sans-serif font is not installed, so use another one
+        let family = ["Helvetica", "DejaVu Sans", "Liberation Sans", "Noto Sans"]
+            .iter()
+            .map(|&name| name.to_string())
+            .find(|name| {
+                fonts
+                    .faces()
+                    .any(|face| face.families.iter().any(|(family, _)| family == name))
+            })
+            .or_else(|| Some(fonts.faces().next()?.families.first()?.0.clone()));
+        if let Some(family) = family {
+            fonts.set_sans_serif_family(family);
+        }
+    }
+    fonts
 });
 
 /// Creates an image of a puzzle with optional markup
@@ -83,6 +113,10 @@ pub struct PuzzleImageBuilder<'a> {
     cell_changes: Option<&'a CellChanges>,
     cell_variables: Option<&'a Square<CellVariable>>,
     solution: Option<&'a Solution>,
+    highlighted_cells: Option<&'a [CellId]>,
+    entries: Option<&'a PlayerGrid>,
+    conflict_cells: Vec<CellId>,
+    theme: &'a ImageTheme,
 }
 
 impl<'a> PuzzleImageBuilder<'a> {
@@ -92,9 +126,19 @@ impl<'a> PuzzleImageBuilder<'a> {
             cell_changes: None,
             solution: None,
             cell_variables: None,
+            highlighted_cells: None,
+            entries: None,
+            conflict_cells: Vec::new(),
+            theme: &DEFAULT_THEME,
         }
     }
 
+    /// Sets the colors, line widths, fonts and sizes of the image
+    pub fn theme(&mut self, theme: &'a ImageTheme) -> &mut Self {
+        self.theme = theme;
+        self
+    }
+
     pub(crate) fn cell_changes(&mut self, cell_changes: &'a CellChanges) -> &mut Self {
         self.cell_changes = Some(cell_changes);
         self
@@ -113,13 +157,52 @@ impl<'a> PuzzleImageBuilder<'a> {
         self
     }
 
+    /// Highlights cells, such as the cells that differ between two solutions
+    pub fn highlighted_cells(&mut self, cells: &'a [CellId]) -> &mut Self {
+        self.highlighted_cells = Some(cells);
+        self
+    }
+
+    /// Shows the values entered in a player's grid
+    pub fn entries(&mut self, grid: &'a PlayerGrid) -> &mut Self {
+        self.entries = Some(grid);
+        self
+    }
+
+    /// Marks the cells of conflicts found in a player's grid
+    pub fn conflicts(&mut self, conflicts: &[Conflict]) -> &mut Self {
+        let mut cells: Vec<CellId> = conflicts
+            .iter()
+            .flat_map(|conflict| conflict.cell_ids(self.puzzle))
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+        self.conflict_cells = cells;
+        self
+    }
+
     pub fn build(self) -> PuzzleImage<'a> {
         let puzzle = self.puzzle;
-        let cells_width = CELL_WIDTH * puzzle.width() as i32;
-        let width = cells_width + OUTER_PAD * 2;
+        let theme = self.theme;
+        let cells_width = theme.cell_width * puzzle.width() as i32;
+        let width = cells_width + theme.outer_pad() * 2;
         let (solutions, domains) = if let Some(cell_variables) = self.cell_variables {
             let (solutions, domains) = Self::solutions_domains(cell_variables, self.cell_changes);
             (solutions.into_boxed_slice(), domains)
+        } else if let Some(grid) = self.entries {
+            let solutions = grid
+                .entries()
+                .iter()
+                .enumerate()
+                .filter_map(|(cell_id, &value)| {
+                    Some(SolutionValue {
+                        cell_id,
+                        value: value?,
+                        is_new: false,
+                    })
+                })
+                .collect();
+            (solutions, HashMap::default())
         } else {
             let solutions = self.solution.map_or_else(Box::default, |solution| {
                 solution
@@ -136,12 +219,17 @@ impl<'a> PuzzleImageBuilder<'a> {
         };
         let changed_cells = self
             .cell_changes
-            .map_or_else(Box::default, |c| c.keys().copied().collect());
+            .into_iter()
+            .flat_map(|c| c.keys().copied())
+            .chain(self.highlighted_cells.into_iter().flatten().copied())
+            .collect();
         PuzzleImage {
             puzzle,
             solutions,
             domains,
             changed_cells,
+            conflict_cells: self.conflict_cells.into_boxed_slice(),
+            theme,
             cells_width,
             width,
         }
@@ -172,8 +260,8 @@ impl<'a> PuzzleImageBuilder<'a> {
             } else if let Some(&CellChange::Solution(value)) = cell_change {
                 Some((value, true))
             } else {
-                match domain_and_removals {
-                    Some((ref domain, ref removals)) if domain.len() - removals.len() == 1 => {
+                match &domain_and_removals {
+                    Some((domain, removals)) if domain.len() - removals.len() == 1 => {
                         // since there is one domain value left, show the solution
                         let value = domain.iter().find(|v| !removals.contains(v)).unwrap();
                         Some((value, true))
@@ -208,6 +296,8 @@ pub struct PuzzleImage<'a> {
     solutions: Box<[SolutionValue]>,
     domains: HashMap<SquareIndex, Vec<DomainValue>>,
     changed_cells: Box<[CellId]>,
+    conflict_cells: Box<[CellId]>,
+    theme: &'a ImageTheme,
     width: i32,
     cells_width: i32,
 }
@@ -223,20 +313,100 @@ struct SolutionValue {
     is_new: bool,
 }
 
+/// The file format of a saved image
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ImageFormat {
+    #[default]
+    Svg,
+    /// A bitmap with the given resolution. One unit of the SVG image is drawn as one pixel at 96
+    /// DPI, so a 4x4 puzzle is about 4.3 inches wide.
+    Png { dpi: f32 },
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png { .. } => "png",
+        }
+    }
+}
+
 impl<'x> PuzzleImage<'x> {
+    pub fn save(&self, path: &Path, format: ImageFormat) -> result::Result<(), SaveImageError> {
+        match format {
+            ImageFormat::Svg => self.save_svg(path)?,
+            ImageFormat::Png { dpi } => self.save_png(path, dpi)?,
+        }
+        Ok(())
+    }
+
     pub fn save_svg(&self, path: &Path) -> io::Result<()> {
-        let xml = XmlProducer::new(|xml| PuzzleSvgContext { image: self, xml }.write());
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
-        write!(writer, "{}", xml)?;
+        write!(writer, "{}", self.svg())?;
         writer.flush()?;
         Ok(())
     }
+
+    /// Draws the image as a bitmap with the given resolution and saves it as a PNG file
+    pub fn save_png(&self, path: &Path, dpi: f32) -> result::Result<(), SaveImageError> {
+        let png = self.render_png(dpi)?;
+        fs::write(path, png)?;
+        Ok(())
+    }
+
+    fn render_png(&self, dpi: f32) -> result::Result<Vec<u8>, SaveImageError> {
+        self.render_pixmap(dpi)?
+            .encode_png()
+            .map_err(|e| SaveImageError::Render { msg: e.to_string() })
+    }
+
+    fn render_pixmap(&self, dpi: f32) -> result::Result<tiny_skia::Pixmap, SaveImageError> {
+        let render_error = |msg: String| SaveImageError::Render { msg };
+        let tree = parse_svg(&self.svg())?;
+        let scale = dpi / SVG_DPI;
+        let size = tree
+            .size
+            .to_int_size()
+            .scale_by(scale)
+            .ok_or_else(|| render_error(format!("invalid DPI: {}", dpi)))?;
+        let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+            .ok_or_else(|| render_error(format!("invalid DPI: {}", dpi)))?;
+        resvg::render(
+            &tree,
+            tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+        Ok(pixmap)
+    }
+
+    fn svg(&self) -> String {
+        XmlProducer::new(|xml| {
+            PuzzleSvgContext {
+                image: self,
+                xml,
+                frame: None,
+            }
+            .write()
+        })
+        .to_string()
+    }
+}
+
+/// The position and size of a puzzle drawn inside a larger SVG image
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    x: f32,
+    y: f32,
+    size: f32,
 }
 
 struct PuzzleSvgContext<'a, 'b, 'c> {
     image: &'a PuzzleImage<'a>,
     xml: &'a mut Xml<'b, 'c>,
+    /// Draws the puzzle as a nested `svg` element, without a style sheet
+    frame: Option<Frame>,
 }
 
 impl PuzzleSvgContext<'_, '_, '_> {
@@ -249,18 +419,36 @@ impl PuzzleSvgContext<'_, '_, '_> {
         self.cages_outline()?;
         self.cage_spec()?;
         self.domain()?;
-        self.solutions()
+        self.solutions()?;
+        xml!(self.xml, close);
+        Ok(())
     }
 
     fn header(&mut self) -> Result {
-        xml! {
-            self.xml,
-            open "svg",
-            "xmlns" = "http://www.w3.org/2000/svg",
-            "viewBox" = format!("0 0 {0} {0}", self.image.width),
-            open "style",
-            text = STYLE.as_str(),
-            close,
+        let view_box = format!("0 0 {0} {0}", self.image.width);
+        match self.frame {
+            None => {
+                xml! {
+                    self.xml,
+                    open "svg",
+                    "xmlns" = "http://www.w3.org/2000/svg",
+                    "viewBox" = view_box,
+                    open "style",
+                    text = style(self.image.theme),
+                    close,
+                }
+            }
+            Some(Frame { x, y, size }) => {
+                xml! {
+                    self.xml,
+                    open "svg",
+                    "x" = x,
+                    "y" = y,
+                    "width" = size,
+                    "height" = size,
+                    "viewBox" = view_box,
+                }
+            }
         }
         Ok(())
     }
@@ -269,30 +457,35 @@ impl PuzzleSvgContext<'_, '_, '_> {
         xml! {
             self.xml,
             open "rect",
-            "x" = OUTER_PAD,
-            "y" = OUTER_PAD,
+            "x" = self.image.theme.outer_pad(),
+            "y" = self.image.theme.outer_pad(),
             "width" = self.image.cells_width,
             "height" = self.image.cells_width,
-            "fill" = "white",
+            "fill" = &self.image.theme.background,
             close,
         }
         Ok(())
     }
 
     fn highlight_cells(&mut self) -> Result {
-        if self.image.changed_cells.is_empty() {
+        self.fill_cells("highlight", &self.image.changed_cells)?;
+        self.fill_cells("conflict", &self.image.conflict_cells)
+    }
+
+    fn fill_cells(&mut self, class: &str, cells: &[CellId]) -> Result {
+        if cells.is_empty() {
             return Ok(());
         }
-        xml!(self.xml, open "g", "class" = "highlight");
-        for &cell_id in &*self.image.changed_cells {
+        xml!(self.xml, open "g", "class" = class);
+        for &cell_id in cells {
             let coord = self.cell_id_coord(cell_id);
             xml! {
                 self.xml,
                 open "rect",
                 "x" = coord.col(),
                 "y" = coord.row(),
-                "width" = CELL_WIDTH,
-                "height" = CELL_WIDTH,
+                "width" = self.image.theme.cell_width,
+                "height" = self.image.theme.cell_width,
                 close,
             }
         }
@@ -303,7 +496,7 @@ impl PuzzleSvgContext<'_, '_, '_> {
     fn grid(&mut self) -> Result {
         let mut d = String::new();
         for i in 1..self.image.puzzle.width() {
-            let coord = cell_coord(Coord::new(0, i));
+            let coord = self.cell_coord(Coord::new(0, i));
             write!(
                 &mut d,
                 "M{0}h{2}M{1}v{2}",
@@ -316,8 +509,8 @@ impl PuzzleSvgContext<'_, '_, '_> {
         xml! {
             self.xml,
             open "path",
-            "stroke" = COLOR_CELL_BORDER,
-            "stroke-width" = BORDER_WIDTH_CELL,
+            "stroke" = &self.image.theme.cell_border,
+            "stroke-width" = self.image.theme.cell_border_width,
             "d" = d,
             close,
         }
@@ -325,8 +518,9 @@ impl PuzzleSvgContext<'_, '_, '_> {
     }
 
     fn outer_border(&mut self) -> Result {
-        let x = BORDER_WIDTH_OUTER / 2;
-        let width = self.image.width - BORDER_WIDTH_OUTER;
+        let theme = self.image.theme;
+        let x = theme.outer_border_width / 2;
+        let width = self.image.width - theme.outer_border_width;
         xml! {
             self.xml,
             open "rect",
@@ -335,8 +529,8 @@ impl PuzzleSvgContext<'_, '_, '_> {
             "width" = width,
             "height" = width,
             "fill" = "none",
-            "stroke" = COLOR_CAGE_BORDER,
-            "stroke-width" = BORDER_WIDTH_OUTER,
+            "stroke" = &theme.cage_border,
+            "stroke-width" = theme.outer_border_width,
             "stroke-linejoin" = "round",
             close,
         }
@@ -395,10 +589,10 @@ impl PuzzleSvgContext<'_, '_, '_> {
                         d,
                         "M{}{}{}",
                         // the second cell's coordinates is where the line starts
-                        path_coord(cell_coord(coord_b(i, j))),
+                        path_coord(self.cell_coord(coord_b(i, j))),
                         // horizontal or vertical
                         draw_char,
-                        CELL_WIDTH * len as i32
+                        self.image.theme.cell_width * len as i32
                     )
                     .unwrap();
                 }
@@ -407,8 +601,8 @@ impl PuzzleSvgContext<'_, '_, '_> {
         xml! {
             self.xml,
             open "path",
-            "stroke" = COLOR_CAGE_BORDER,
-            "stroke-width" = BORDER_WIDTH_CAGE,
+            "stroke" = &self.image.theme.cage_border,
+            "stroke-width" = self.image.theme.cage_border_width,
             "stroke-linecap" = "round",
             "d" = d,
             close,
@@ -423,12 +617,13 @@ impl PuzzleSvgContext<'_, '_, '_> {
                 Some(symbol) => format!("{}{}", cage.target(), symbol),
                 None => cage.target().to_string(),
             };
-            let pos = cell_coord(cage.coord());
+            let pos = self.cell_coord(cage.coord());
+            let pad = self.image.theme.cage_spec_pad();
             xml!(
                 self.xml,
                 open "text",
-                "x" = pos.col() + CAGE_SPEC_PAD,
-                "y" = pos.row() + CAGE_SPEC_PAD,
+                "x" = pos.col() + pad,
+                "y" = pos.row() + pad,
                 "dy" = ".8em",
                 text = text,
                 close,
@@ -445,6 +640,7 @@ impl PuzzleSvgContext<'_, '_, '_> {
 
         xml!(self.xml, open "g", "class" = "domain");
 
+        let theme = self.image.theme;
         let mut removals = Vec::new();
 
         for (&cell_id, domain) in &self.image.domains {
@@ -456,8 +652,10 @@ impl PuzzleSvgContext<'_, '_, '_> {
             let mut char_x = 0;
             let mut char_y = 0;
             for &DomainValue { value, removed } in domain {
-                let x = coord.col() + DOMAIN_PAD + char_x * DOMAIN_DX;
-                let y = coord.row() + CELL_WIDTH - DOMAIN_PAD - char_y * FONT_SIZE_DOMAIN;
+                let x = coord.col() + theme.domain_pad() + char_x * theme.domain_dx();
+                let y = coord.row() + theme.cell_width
+                    - theme.domain_pad()
+                    - char_y * theme.domain_font_size;
                 xml! {
                     self.xml,
                     open "text",
@@ -485,15 +683,15 @@ impl PuzzleSvgContext<'_, '_, '_> {
                     "M{},{}l{},{}",
                     x,
                     y,
-                    FONT_SIZE_DOMAIN / 2,
-                    -(FONT_SIZE_DOMAIN * 5 / 7),
+                    theme.domain_font_size / 2,
+                    -(theme.domain_font_size * 5 / 7),
                 )
                 .unwrap();
             }
             xml! {
                 self.xml,
                 open "path",
-                "stroke" = COLOR_DOMAIN_SLASH,
+                "stroke" = &theme.domain_slash,
                 "stroke-width" = DOMAIN_SLASH_WIDTH,
                 "stroke-linecap" = "round",
                 "d" = d,
@@ -521,8 +719,8 @@ impl PuzzleSvgContext<'_, '_, '_> {
                 if is_new {
                     "class" = "new-solution",
                 }
-                "x" = coord.col() + CELL_WIDTH / 2,
-                "y" = coord.row() + CELL_WIDTH / 2,
+                "x" = coord.col() + self.image.theme.cell_width / 2,
+                "y" = coord.row() + self.image.theme.cell_width / 2,
                 "dy" = ".35em",
                 text = value,
                 close,
@@ -533,17 +731,43 @@ impl PuzzleSvgContext<'_, '_, '_> {
     }
 
     fn cell_id_coord(&self, cell_id: CellId) -> Coord<i32> {
-        cell_coord(self.image.puzzle.cell(cell_id).coord())
+        self.cell_coord(self.image.puzzle.cell(cell_id).coord())
+    }
+
+    fn cell_coord(&self, coord: Coord<SquareValue>) -> Coord<i32> {
+        let theme = self.image.theme;
+        Coord::new(
+            coord.col() as i32 * theme.cell_width + theme.outer_pad(),
+            coord.row() as i32 * theme.cell_width + theme.outer_pad(),
+        )
     }
 }
 
-fn cell_coord(coord: Coord<SquareValue>) -> Coord<i32> {
-    Coord::new(
-        coord.col() as i32 * CELL_WIDTH + OUTER_PAD,
-        coord.row() as i32 * CELL_WIDTH + OUTER_PAD,
-    )
+/// Parses an SVG image and converts its text to paths with the system fonts
+fn parse_svg(svg: &str) -> result::Result<usvg::Tree, SaveImageError> {
+    let mut tree = usvg::Tree::from_str(svg, &usvg::Options::default())
+        .map_err(|e| SaveImageError::Render { msg: e.to_string() })?;
+    tree.postprocess(usvg::PostProcessingSteps::default(), &FONTS);
+    Ok(tree)
 }
 
 fn path_coord(coord: Coord<i32>) -> String {
     format!("{},{}", coord.col(), coord.row())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::image::PuzzleImageBuilder;
+    use crate::puzzle::Puzzle;
+
+    #[test]
+    fn png_dpi() {
+        let puzzle = Puzzle::parse("2\nAB\nCC\n1 2 3+").unwrap();
+        let image = PuzzleImageBuilder::new(&puzzle).build();
+        let png = image.render_png(192.0).unwrap();
+        assert_eq!(b"\x89PNG", &png[..4]);
+        // the width in the header is twice the width of the SVG image
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+        assert_eq!(image.width as u32 * 2, width);
+    }
+}
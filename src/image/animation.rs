@@ -0,0 +1,136 @@
+//! Combine puzzle images into an animated GIF
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use resvg::tiny_skia::{Color, Pixmap, PixmapPaint, Transform};
+
+use crate::error::SaveImageError;
+use crate::image::{PuzzleImage, SVG_DPI};
+
+/// How many times longer than other frames the last frame is shown before the animation repeats
+const LAST_FRAME_DELAY_FACTOR: u32 = 5;
+
+/// Quality of color quantization, from 1 (best) to 30 (fastest)
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Writes puzzle images to an animated GIF file, one frame at a time. Every image must have the
+/// same size. The animation repeats forever.
+pub struct GifWriter {
+    path: PathBuf,
+    frame_delay: Duration,
+    encoder: Option<gif::Encoder<BufWriter<File>>>,
+    /// The last frame is held back so that it can be shown for longer
+    pending: Option<gif::Frame<'static>>,
+}
+
+impl GifWriter {
+    /// Creates a writer that shows each frame for `frame_delay`. The file is created when the
+    /// first frame is added.
+    pub fn new(path: &Path, frame_delay: Duration) -> Self {
+        Self {
+            path: path.into(),
+            frame_delay,
+            encoder: None,
+            pending: None,
+        }
+    }
+
+    pub fn add_frame(&mut self, image: &PuzzleImage<'_>) -> Result<(), SaveImageError> {
+        let image_pixmap = image.render_pixmap(SVG_DPI)?;
+        let (width, height) = (image_pixmap.width(), image_pixmap.height());
+        // GIF has no partial transparency, so draw on a white background
+        let mut pixmap = Pixmap::new(width, height).unwrap();
+        pixmap.fill(Color::WHITE);
+        pixmap.draw_pixmap(
+            0,
+            0,
+            image_pixmap.as_ref(),
+            &PixmapPaint::default(),
+            Transform::identity(),
+            None,
+        );
+        let (width, height) = (width as u16, height as u16);
+        let mut frame =
+            gif::Frame::from_rgba_speed(width, height, pixmap.data_mut(), QUANTIZE_SPEED);
+        frame.delay = delay_centis(self.frame_delay);
+        if self.encoder.is_none() {
+            let writer = BufWriter::new(File::create(&self.path)?);
+            let mut encoder =
+                gif::Encoder::new(writer, width, height, &[]).map_err(encoding_error)?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(encoding_error)?;
+            self.encoder = Some(encoder);
+        }
+        if let Some(pending) = self.pending.replace(frame) {
+            self.encoder
+                .as_mut()
+                .unwrap()
+                .write_frame(&pending)
+                .map_err(encoding_error)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the last frame and finishes the file. Nothing is written if there are no frames.
+    pub fn finish(mut self) -> Result<(), SaveImageError> {
+        let (mut encoder, mut frame) = match (self.encoder.take(), self.pending.take()) {
+            (Some(encoder), Some(frame)) => (encoder, frame),
+            _ => return Ok(()),
+        };
+        frame.delay = delay_centis(self.frame_delay * LAST_FRAME_DELAY_FACTOR);
+        encoder.write_frame(&frame).map_err(encoding_error)?;
+        let mut writer = encoder.into_inner()?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// GIF frame delays are in hundredths of a second
+fn delay_centis(delay: Duration) -> u16 {
+    (delay.as_millis() / 10).min(u16::MAX.into()) as u16
+}
+
+fn encoding_error(e: gif::EncodingError) -> SaveImageError {
+    match e {
+        gif::EncodingError::Io(e) => SaveImageError::Io(e),
+        e => SaveImageError::Render { msg: e.to_string() },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::image::{GifWriter, PuzzleImageBuilder};
+    use crate::puzzle::Puzzle;
+    use crate::solve::tests::solution;
+
+    #[test]
+    fn frames() {
+        let puzzle = Puzzle::parse("2\nAB\nCC\n1 2 3+").unwrap();
+        let solution = solution(&puzzle);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("solve.gif");
+        let mut writer = GifWriter::new(&path, Duration::from_millis(200));
+        writer
+            .add_frame(&PuzzleImageBuilder::new(&puzzle).build())
+            .unwrap();
+        let mut builder = PuzzleImageBuilder::new(&puzzle);
+        builder.solution(&solution);
+        writer.add_frame(&builder.build()).unwrap();
+        writer.finish().unwrap();
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(std::fs::File::open(&path).unwrap())
+            .unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        assert_eq!(vec![20, 100], delays);
+    }
+}
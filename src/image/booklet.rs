@@ -0,0 +1,386 @@
+//! Lay out many puzzles on the pages of a PDF booklet
+
+use std::fmt::Result;
+use std::path::Path;
+use std::{fs, result};
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+
+use crate::error::SaveImageError;
+use crate::image::xml::{Xml, XmlProducer};
+use crate::image::{
+    parse_svg, style, Frame, ImageTheme, PuzzleImageBuilder, PuzzleSvgContext, DEFAULT_THEME,
+};
+use crate::puzzle::{Puzzle, Solution};
+use crate::solve::DifficultyTier;
+
+// dimensions in points (1/72 inch)
+const PAGE_MARGIN: f32 = 36.0;
+const TITLE_HEIGHT: f32 = 40.0;
+const FOOTER_HEIGHT: f32 = 24.0;
+const LABEL_HEIGHT: f32 = 18.0;
+const SLOT_GAP: f32 = 18.0;
+
+// font sizes
+const FONT_SIZE_TITLE: f32 = 20.0;
+const FONT_SIZE_LABEL: f32 = 11.0;
+const FONT_SIZE_PAGE_NUMBER: f32 = 10.0;
+
+/// The style of puzzles and the text around them. Text on the page is black since the page is
+/// white, whatever the theme.
+fn booklet_style(theme: &ImageTheme) -> String {
+    format!(
+        "{style}\
+        .booklet-title{{\
+          fill:black;\
+          font-size:{title_font_size}px;\
+          font-weight:bold;\
+          text-anchor:middle\
+        }}\
+        .booklet-label{{\
+          fill:black;\
+          font-size:{label_font_size}px\
+        }}\
+        .booklet-difficulty{{\
+          fill:black;\
+          font-size:{label_font_size}px;\
+          font-style:italic;\
+          text-anchor:end\
+        }}\
+        .booklet-page-number{{\
+          fill:black;\
+          font-size:{page_number_font_size}px;\
+          text-anchor:middle\
+        }}",
+        style = style(theme),
+        title_font_size = FONT_SIZE_TITLE,
+        label_font_size = FONT_SIZE_LABEL,
+        page_number_font_size = FONT_SIZE_PAGE_NUMBER,
+    )
+}
+
+/// The size of the pages of a booklet
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PageSize {
+    /// 8.5 by 11 inches
+    #[default]
+    Letter,
+    /// 210 by 297 millimeters
+    A4,
+}
+
+impl PageSize {
+    /// The width and height in points
+    fn dimensions(self) -> (f32, f32) {
+        match self {
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::A4 => (595.0, 842.0),
+        }
+    }
+}
+
+struct BookletPuzzle<'a> {
+    puzzle: &'a Puzzle,
+    solution: Option<&'a Solution>,
+    difficulty: Option<DifficultyTier>,
+}
+
+/// Creates a PDF booklet with a grid of puzzles on each page, followed by pages with the answers.
+/// Puzzles are drawn the same way as `PuzzleImage`.
+pub struct BookletBuilder<'a> {
+    title: String,
+    columns: u32,
+    rows: u32,
+    page_size: PageSize,
+    theme: &'a ImageTheme,
+    puzzles: Vec<BookletPuzzle<'a>>,
+}
+
+impl<'a> BookletBuilder<'a> {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            columns: 2,
+            rows: 2,
+            page_size: PageSize::default(),
+            theme: &DEFAULT_THEME,
+            puzzles: Vec::new(),
+        }
+    }
+
+    /// Sets the number of puzzles across and down each page, 2 by 2 by default. Both must be at
+    /// least 1, which is checked when the booklet is drawn.
+    pub fn layout(&mut self, columns: u32, rows: u32) -> &mut Self {
+        self.columns = columns;
+        self.rows = rows;
+        self
+    }
+
+    pub fn page_size(&mut self, page_size: PageSize) -> &mut Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Sets the style of the puzzles
+    pub fn theme(&mut self, theme: &'a ImageTheme) -> &mut Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Adds a puzzle to the booklet. Puzzles with a solution are included in the answer pages.
+    pub fn puzzle(
+        &mut self,
+        puzzle: &'a Puzzle,
+        solution: Option<&'a Solution>,
+        difficulty: Option<DifficultyTier>,
+    ) -> &mut Self {
+        self.puzzles.push(BookletPuzzle {
+            puzzle,
+            solution,
+            difficulty,
+        });
+        self
+    }
+
+    pub fn save_pdf(&self, path: &Path) -> result::Result<(), SaveImageError> {
+        fs::write(path, self.pdf()?)?;
+        Ok(())
+    }
+
+    /// Draws every page and writes them to a PDF document
+    pub fn pdf(&self) -> result::Result<Vec<u8>, SaveImageError> {
+        if self.columns == 0 || self.rows == 0 {
+            return Err(SaveImageError::InvalidLayout {
+                msg: format!(
+                    "{}x{} does not fit any puzzles on a page",
+                    self.columns, self.rows
+                ),
+            });
+        }
+        let (page_width, page_height) = self.page_size.dimensions();
+        let pages = self.page_svgs();
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let mut next_id = Ref::new(3);
+        let mut page_ids = Vec::with_capacity(pages.len());
+        let svg_name = Name(b"S1");
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        for svg in &pages {
+            let tree = parse_svg(svg)?;
+            let page_id = next_id.bump();
+            let content_id = next_id.bump();
+            let svg_id = next_id.bump();
+            page_ids.push(page_id);
+            next_id =
+                svg2pdf::convert_tree_into(&tree, svg2pdf::Options::default(), &mut pdf, svg_id);
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, page_width, page_height));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            page.resources().x_objects().pair(svg_name, svg_id);
+            page.finish();
+            let mut content = Content::new();
+            content
+                .transform([page_width, 0.0, 0.0, page_height, 0.0, 0.0])
+                .x_object(svg_name);
+            pdf.stream(content_id, &content.finish());
+        }
+        let page_count = page_ids.len() as i32;
+        pdf.pages(page_tree_id).kids(page_ids).count(page_count);
+        Ok(pdf.finish())
+    }
+
+    /// Draws every page as an SVG image. The puzzles come first, then the answers.
+    fn page_svgs(&self) -> Vec<String> {
+        let per_page = (self.columns * self.rows) as usize;
+        let numbered: Vec<(usize, &BookletPuzzle<'a>)> = self
+            .puzzles
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i + 1, p))
+            .collect();
+        let answers: Vec<(usize, &BookletPuzzle<'a>)> = numbered
+            .iter()
+            .copied()
+            .filter(|(_, p)| p.solution.is_some())
+            .collect();
+        let puzzle_pages = numbered.chunks(per_page).map(|chunk| (chunk, false));
+        let answer_pages = answers.chunks(per_page).map(|chunk| (chunk, true));
+        let pages: Vec<_> = puzzle_pages.chain(answer_pages).collect();
+        pages
+            .iter()
+            .enumerate()
+            .map(|(i, &(puzzles, is_answers))| {
+                let title = if is_answers {
+                    format!("{} \u{2013} Answers", self.title)
+                } else {
+                    self.title.clone()
+                };
+                XmlProducer::new(|xml| {
+                    PageSvgContext {
+                        builder: self,
+                        xml,
+                        title: &title,
+                        page_number: i + 1,
+                    }
+                    .write(puzzles, is_answers)
+                })
+                .to_string()
+            })
+            .collect()
+    }
+}
+
+struct PageSvgContext<'a, 'b, 'c, 'd> {
+    builder: &'a BookletBuilder<'a>,
+    xml: &'a mut Xml<'b, 'c>,
+    title: &'d str,
+    page_number: usize,
+}
+
+impl PageSvgContext<'_, '_, '_, '_> {
+    fn write(mut self, puzzles: &[(usize, &BookletPuzzle<'_>)], is_answers: bool) -> Result {
+        let (page_width, page_height) = self.builder.page_size.dimensions();
+        xml! {
+            self.xml,
+            open "svg",
+            "xmlns" = "http://www.w3.org/2000/svg",
+            "width" = page_width,
+            "height" = page_height,
+            "viewBox" = format!("0 0 {} {}", page_width, page_height),
+            open "style",
+            text = booklet_style(self.builder.theme),
+            close,
+            open "rect",
+            "width" = page_width,
+            "height" = page_height,
+            "fill" = "white",
+            close,
+            open "text",
+            "class" = "booklet-title",
+            "x" = page_width / 2.0,
+            "y" = PAGE_MARGIN + FONT_SIZE_TITLE,
+            text = escape(self.title),
+            close,
+            open "text",
+            "class" = "booklet-page-number",
+            "x" = page_width / 2.0,
+            "y" = page_height - PAGE_MARGIN,
+            text = self.page_number,
+            close,
+        }
+        for (i, &(number, puzzle)) in puzzles.iter().enumerate() {
+            self.slot(i as u32, number, puzzle, is_answers)?;
+        }
+        xml!(self.xml, close);
+        Ok(())
+    }
+
+    /// Draws a puzzle with its number and difficulty at a position in the grid of the page
+    fn slot(
+        &mut self,
+        index: u32,
+        number: usize,
+        puzzle: &BookletPuzzle<'_>,
+        is_answers: bool,
+    ) -> Result {
+        let BookletBuilder { columns, rows, .. } = *self.builder;
+        let (page_width, page_height) = self.builder.page_size.dimensions();
+        let slot_width =
+            (page_width - PAGE_MARGIN * 2.0 - SLOT_GAP * (columns - 1) as f32) / columns as f32;
+        let slot_height = (page_height
+            - PAGE_MARGIN * 2.0
+            - TITLE_HEIGHT
+            - FOOTER_HEIGHT
+            - SLOT_GAP * (rows - 1) as f32)
+            / rows as f32;
+        let size = slot_width.min(slot_height - LABEL_HEIGHT);
+        let (col, row) = (index % columns, index / columns);
+        let x = PAGE_MARGIN + col as f32 * (slot_width + SLOT_GAP) + (slot_width - size) / 2.0;
+        let y = PAGE_MARGIN + TITLE_HEIGHT + row as f32 * (slot_height + SLOT_GAP);
+        let label_y = y + FONT_SIZE_LABEL;
+        xml! {
+            self.xml,
+            open "text",
+            "class" = "booklet-label",
+            "x" = x,
+            "y" = label_y,
+            text = format!("Puzzle {}", number),
+            close,
+        }
+        if let Some(difficulty) = puzzle.difficulty.filter(|_| !is_answers) {
+            xml! {
+                self.xml,
+                open "text",
+                "class" = "booklet-difficulty",
+                "x" = x + size,
+                "y" = label_y,
+                text = difficulty,
+                close,
+            }
+        }
+        let mut builder = PuzzleImageBuilder::new(puzzle.puzzle);
+        builder.theme(self.builder.theme);
+        if is_answers {
+            builder.solution(puzzle.solution.unwrap());
+        }
+        let image = builder.build();
+        PuzzleSvgContext {
+            image: &image,
+            xml: self.xml,
+            frame: Some(Frame {
+                x,
+                y: y + LABEL_HEIGHT,
+                size,
+            }),
+        }
+        .write()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::BookletBuilder;
+    use crate::puzzle::Puzzle;
+    use crate::solve::tests::solution;
+    use crate::solve::DifficultyTier;
+
+    #[test]
+    fn pages() {
+        let puzzle = Puzzle::parse("2\nAB\nCC\n1 2 3+").unwrap();
+        let solution = solution(&puzzle);
+        let mut builder = BookletBuilder::new("Tom & Jerry");
+        builder.layout(2, 1);
+        for _ in 0..3 {
+            builder.puzzle(&puzzle, Some(&solution), Some(DifficultyTier::Easy));
+        }
+        builder.puzzle(&puzzle, None, None);
+        let pages = builder.page_svgs();
+        // two pages of puzzles and two pages of answers for the three solved puzzles
+        assert_eq!(4, pages.len());
+        assert!(pages[0].contains("Tom &amp; Jerry"));
+        assert!(pages[1].contains("Puzzle 4"));
+        assert!(pages[2].contains("Answers"));
+        assert!(pages[3].contains("Puzzle 3"));
+        assert!(!pages[3].contains("Puzzle 4"));
+
+        let pdf = builder.pdf().unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+        let text = String::from_utf8_lossy(&pdf);
+        assert_eq!(4, text.matches("/Type /Page\n").count());
+    }
+
+    #[test]
+    fn invalid_layout() {
+        let mut builder = BookletBuilder::new("Puzzles");
+        builder.layout(0, 2);
+        assert!(builder.pdf().is_err());
+    }
+}
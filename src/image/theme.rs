@@ -0,0 +1,291 @@
+//! Colors, line widths, fonts and sizes of puzzle images
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{ParseThemeError, ThemeFromFileError};
+
+/// The style of puzzle images. Colors are any CSS color, like "black" or "#CCC". Sizes are in
+/// units of the SVG image, which are pixels in PNG images at 96 DPI.
+///
+/// A theme file has one `key = value` line for each value that differs from the default theme.
+/// Keys are the field names with `-` instead of `_`, like `cage-border = navy`. A line with
+/// `base = dark` or `base = high-contrast` starts from another preset and should come first.
+/// Lines starting with `#` are comments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageTheme {
+    /// The fill of the cells
+    pub background: String,
+    /// Cage targets and solution values
+    pub text: String,
+    /// Values solved in the current step
+    pub new_solution: String,
+    pub cage_border: String,
+    pub cell_border: String,
+    /// Cells changed in the current step or that differ between solutions
+    pub highlight: String,
+    /// Cells with a conflict in a checked grid
+    pub conflict: String,
+    /// The possible values of unsolved cells
+    pub domain: String,
+    /// Lines through domain values that are removed in the current step
+    pub domain_slash: String,
+    /// A CSS font family list, like "Helvetica, sans-serif". Fonts must be installed to be used
+    /// in PNG images and PDF booklets.
+    pub font_family: String,
+    pub cell_width: i32,
+    pub cell_border_width: i32,
+    pub cage_border_width: i32,
+    pub outer_border_width: i32,
+    pub solution_font_size: i32,
+    pub cage_spec_font_size: i32,
+    pub domain_font_size: i32,
+}
+
+impl Default for ImageTheme {
+    fn default() -> Self {
+        Self {
+            background: "white".into(),
+            text: "black".into(),
+            new_solution: "green".into(),
+            cage_border: "black".into(),
+            cell_border: "#CCC".into(),
+            highlight: "#FFC".into(),
+            conflict: "#FCC".into(),
+            domain: "#444".into(),
+            domain_slash: "red".into(),
+            font_family: "sans-serif".into(),
+            cell_width: 100,
+            cell_border_width: 2,
+            cage_border_width: 4,
+            outer_border_width: 6,
+            solution_font_size: 64,
+            cage_spec_font_size: 24,
+            domain_font_size: 20,
+        }
+    }
+}
+
+impl ImageTheme {
+    /// Light lines and text on a dark background
+    pub fn dark() -> Self {
+        Self {
+            background: "#222".into(),
+            text: "#EEE".into(),
+            new_solution: "#7D7".into(),
+            cage_border: "#EEE".into(),
+            cell_border: "#555".into(),
+            highlight: "#553".into(),
+            conflict: "#733".into(),
+            domain: "#AAA".into(),
+            domain_slash: "#F77".into(),
+            ..Self::default()
+        }
+    }
+
+    /// Black and white with heavier lines, for printing and for readability
+    pub fn high_contrast() -> Self {
+        Self {
+            new_solution: "black".into(),
+            cell_border: "#777".into(),
+            highlight: "#DDD".into(),
+            conflict: "#BBB".into(),
+            domain: "black".into(),
+            domain_slash: "black".into(),
+            cage_border_width: 6,
+            outer_border_width: 8,
+            ..Self::default()
+        }
+    }
+
+    /// Finds a preset theme by name: "default", "dark" or "high-contrast"
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "dark" => Some(Self::dark()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ThemeFromFileError> {
+        let str = fs::read_to_string(path)?;
+        let theme = str.parse()?;
+        Ok(theme)
+    }
+
+    /// Checks that the outer border is at least as wide as the other borders, so that the
+    /// grid lines are drawn inside the image
+    fn check_border_widths(&self) -> Result<(), String> {
+        for (name, width) in [
+            ("cage", self.cage_border_width),
+            ("cell", self.cell_border_width),
+        ] {
+            if self.outer_border_width < width {
+                return Err(format!(
+                    "the outer border width ({}) is less than the {} border width ({})",
+                    self.outer_border_width, name, width
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The space between the edge of the image and the first cell
+    pub(crate) fn outer_pad(&self) -> i32 {
+        self.outer_border_width - self.cell_border_width / 2
+    }
+
+    /// The space between the corner of a cell and its cage target
+    pub(crate) fn cage_spec_pad(&self) -> i32 {
+        self.cell_border_width + self.cell_width / 16
+    }
+
+    /// The space between the edge of a cell and its domain values
+    pub(crate) fn domain_pad(&self) -> i32 {
+        self.cell_width / 20
+    }
+
+    /// The horizontal distance between domain values
+    pub(crate) fn domain_dx(&self) -> i32 {
+        self.domain_font_size * 3 / 4
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let color = |field: &mut String| -> Result<(), String> {
+            if !value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "#(),.% ".contains(c))
+            {
+                return Err(format!("invalid color: \"{}\"", value));
+            }
+            *field = value.into();
+            Ok(())
+        };
+        let size = |field: &mut i32| -> Result<(), String> {
+            match value.parse() {
+                Ok(size) if size > 0 => {
+                    *field = size;
+                    Ok(())
+                }
+                _ => Err(format!("invalid size: \"{}\"", value)),
+            }
+        };
+        match key {
+            "base" => {
+                *self =
+                    Self::preset(value).ok_or_else(|| format!("unknown theme: \"{}\"", value))?;
+                Ok(())
+            }
+            "background" => color(&mut self.background),
+            "text" => color(&mut self.text),
+            "new-solution" => color(&mut self.new_solution),
+            "cage-border" => color(&mut self.cage_border),
+            "cell-border" => color(&mut self.cell_border),
+            "highlight" => color(&mut self.highlight),
+            "conflict" => color(&mut self.conflict),
+            "domain" => color(&mut self.domain),
+            "domain-slash" => color(&mut self.domain_slash),
+            "font-family" => {
+                if !value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || ",-_' ".contains(c))
+                {
+                    return Err(format!("invalid font family: \"{}\"", value));
+                }
+                self.font_family = value.into();
+                Ok(())
+            }
+            "cell-width" => size(&mut self.cell_width),
+            "cell-border-width" => size(&mut self.cell_border_width),
+            "cage-border-width" => size(&mut self.cage_border_width),
+            "outer-border-width" => size(&mut self.outer_border_width),
+            "solution-font-size" => size(&mut self.solution_font_size),
+            "cage-spec-font-size" => size(&mut self.cage_spec_font_size),
+            "domain-font-size" => size(&mut self.domain_font_size),
+            _ => Err(format!("unknown key: \"{}\"", key)),
+        }
+    }
+}
+
+impl FromStr for ImageTheme {
+    type Err = ParseThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut theme = Self::default();
+        // the last line that changed a border width, where an invalid width is reported
+        let mut border_line = 0;
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |msg| ParseThemeError::new(i + 1, msg);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `key = value`".into()))?;
+            let key = key.trim();
+            theme.set(key, value.trim()).map_err(error)?;
+            if key == "base" || key.ends_with("border-width") {
+                border_line = i + 1;
+            }
+        }
+        theme
+            .check_border_widths()
+            .map_err(|msg| ParseThemeError::new(border_line, msg))?;
+        Ok(theme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::ImageTheme;
+
+    #[test]
+    fn parse() {
+        let theme: ImageTheme = "\
+            # a dark theme with blue cages
+            base = dark
+            cage-border = #36C
+            cell-width = 80
+            font-family = 'DejaVu Serif', serif
+            "
+        .parse()
+        .unwrap();
+        assert_eq!(
+            ImageTheme {
+                cage_border: "#36C".into(),
+                cell_width: 80,
+                font_family: "'DejaVu Serif', serif".into(),
+                ..ImageTheme::dark()
+            },
+            theme
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        let error = "text = red\ncell-width = 0"
+            .parse::<ImageTheme>()
+            .unwrap_err();
+        assert_eq!(
+            "invalid theme on line 2: invalid size: \"0\"",
+            error.to_string()
+        );
+        let error = "text = red;}".parse::<ImageTheme>().unwrap_err();
+        assert_eq!(
+            "invalid theme on line 1: invalid color: \"red;}\"",
+            error.to_string()
+        );
+        assert!("colour = red".parse::<ImageTheme>().is_err());
+        let error = "outer-border-width = 8\ncage-border-width = 10"
+            .parse::<ImageTheme>()
+            .unwrap_err();
+        assert_eq!(
+            "invalid theme on line 2: the outer border width (8) is less than the cage border \
+            width (10)",
+            error.to_string()
+        );
+    }
+}
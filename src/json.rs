@@ -0,0 +1,448 @@
+//! Read and write puzzles, solutions and solve results as JSON
+//!
+//! Enabled by the `json` feature. The schema:
+//!
+//! * **Puzzle** - `{"width": 4, "cages": [Cage, ...], "metadata": {"title": "Monday", ...}}`.
+//!   Metadata values are strings, and `metadata` is left out when there is none.
+//! * **Cage** - `{"cells": [Cell, ...], "operator": "add", "target": 7}`. The operator is one of
+//!   `add`, `subtract`, `multiply`, `divide` or `nop` (no operator shown). A cage on its own does
+//!   not know the puzzle width, so it is written with cell IDs instead, like
+//!   `{"cell_ids": [0, 1, 4], "operator": "add", "target": 7}`, where IDs count the cells row by
+//!   row. The cells are checked against a width when the cage is added to a puzzle.
+//! * **Cell** - `{"row": 0, "col": 2}`, counting from the top left corner
+//! * **Solution** - an array of rows, each an array of values, like `[[1, 2], [2, 1]]`
+//! * **SolveResult** - an object with a `status` of `unsolvable`, `solved` or
+//!   `multiple_solutions`:
+//!   * `solved` has `solution`, `used_search`, `difficulty` and `stats`. The difficulty is
+//!     `{"tier": "easy", "score": 12}` and is derived from the stats when reading.
+//!     Stats are `{"constraint_counts": {"VectorSolvedCell": 3, ...}, "search_guesses": 0,
+//!     "search_depth": 0}`, where missing constraint counts are 0.
+//!   * `multiple_solutions` has `solutions`, an array of two solutions, and `differing_cells`, the
+//!     cells with different values in the two solutions, which is derived from the solutions when
+//!     reading.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::de::{Error as _, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::collections::square::{IsSquare, Square, SquareValue};
+use crate::error::PuzzleFromFileError;
+use crate::parse::MAX_PUZZLE_SIZE;
+use crate::puzzle::{Cage, CellId, Metadata, Operator, Puzzle};
+use crate::solve::{
+    ConstraintKind, Difficulty, MultipleSolutionsData, SolveResult, SolveStats, SolvedData,
+};
+
+impl Puzzle {
+    pub fn from_json(str: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(str)
+    }
+
+    pub fn from_json_file(path: &Path) -> Result<Self, PuzzleFromFileError> {
+        let str = fs::read_to_string(path)?;
+        let puzzle = Self::from_json(&str)?;
+        Ok(puzzle)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CellJson {
+    row: SquareValue,
+    col: SquareValue,
+}
+
+impl CellJson {
+    fn new(width: SquareValue, cell_id: usize) -> Self {
+        let width = width as usize;
+        Self {
+            row: (cell_id / width) as SquareValue,
+            col: (cell_id % width) as SquareValue,
+        }
+    }
+
+    fn cell_id<E: serde::de::Error>(&self, width: SquareValue) -> Result<usize, E> {
+        if self.row >= width || self.col >= width {
+            return Err(E::custom(format!(
+                "cell ({}, {}) is outside of the puzzle",
+                self.row, self.col
+            )));
+        }
+        Ok(self.row as usize * width as usize + self.col as usize)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum OperatorJson {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Nop,
+}
+
+impl From<Operator> for OperatorJson {
+    fn from(operator: Operator) -> Self {
+        match operator {
+            Operator::Add => OperatorJson::Add,
+            Operator::Subtract => OperatorJson::Subtract,
+            Operator::Multiply => OperatorJson::Multiply,
+            Operator::Divide => OperatorJson::Divide,
+            Operator::Nop => OperatorJson::Nop,
+        }
+    }
+}
+
+impl From<OperatorJson> for Operator {
+    fn from(operator: OperatorJson) -> Self {
+        match operator {
+            OperatorJson::Add => Operator::Add,
+            OperatorJson::Subtract => Operator::Subtract,
+            OperatorJson::Multiply => Operator::Multiply,
+            OperatorJson::Divide => Operator::Divide,
+            OperatorJson::Nop => Operator::Nop,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CageJson {
+    cells: Vec<CellJson>,
+    operator: OperatorJson,
+    target: i32,
+}
+
+/// A cage on its own, outside of a puzzle
+#[derive(Deserialize, Serialize)]
+struct CageIdsJson {
+    cell_ids: Vec<CellId>,
+    operator: OperatorJson,
+    target: i32,
+}
+
+#[derive(Deserialize, Serialize)]
+struct PuzzleJson {
+    width: SquareValue,
+    cages: Vec<CageJson>,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
+}
+
+impl Serialize for Metadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Metadata {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MetadataVisitor;
+
+        impl<'de> Visitor<'de> for MetadataVisitor {
+            type Value = Metadata;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of strings")
+            }
+
+            // a visitor keeps the order of the keys
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Metadata, A::Error> {
+                let mut metadata = Metadata::new();
+                while let Some((key, value)) = map.next_entry::<String, String>()? {
+                    metadata
+                        .try_insert(&key, &value)
+                        .map_err(A::Error::custom)?;
+                }
+                Ok(metadata)
+            }
+        }
+
+        deserializer.deserialize_map(MetadataVisitor)
+    }
+}
+
+impl Serialize for Cage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CageIdsJson {
+            cell_ids: self.cell_ids().to_vec(),
+            operator: self.operator().into(),
+            target: self.target(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = CageIdsJson::deserialize(deserializer)?;
+        Cage::new(json.cell_ids, json.operator.into(), json.target).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Puzzle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let width = self.width();
+        let cages = self.cages().map(|cage| CageJson {
+            cells: cage
+                .cell_ids()
+                .iter()
+                .map(|&id| CellJson::new(width, id))
+                .collect(),
+            operator: cage.operator().into(),
+            target: cage.target(),
+        });
+        PuzzleJson {
+            width,
+            cages: cages.collect(),
+            metadata: self.metadata().clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Puzzle {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = PuzzleJson::deserialize(deserializer)?;
+        if json.width > MAX_PUZZLE_SIZE {
+            return Err(D::Error::custom("puzzle width is too big"));
+        }
+        let cages = json
+            .cages
+            .into_iter()
+            .map(|cage| {
+                let cell_ids = cage
+                    .cells
+                    .iter()
+                    .map(|cell| cell.cell_id(json.width))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Cage::new(cell_ids, cage.operator.into(), cage.target).map_err(D::Error::custom)
+            })
+            .collect::<Result<_, _>>()?;
+        let mut puzzle = Puzzle::new(json.width, cages).map_err(D::Error::custom)?;
+        *puzzle.metadata_mut() = json.metadata;
+        Ok(puzzle)
+    }
+}
+
+impl<T: Serialize> Serialize for Square<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.rows())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Square<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = Vec::<Vec<T>>::deserialize(deserializer)?;
+        if rows.len() > MAX_PUZZLE_SIZE as usize {
+            return Err(D::Error::custom("square width is too big"));
+        }
+        if rows.iter().any(|row| row.len() != rows.len()) {
+            return Err(D::Error::custom(
+                "every row must have as many values as there are rows",
+            ));
+        }
+        let square = Square::from_iter(rows.into_iter().flatten());
+        Ok(square.unwrap_or_else(|_| unreachable!("rows are checked to form a square")))
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct DifficultyJson {
+    tier: String,
+    score: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+struct SolveStatsJson {
+    constraint_counts: BTreeMap<String, u32>,
+    search_guesses: u32,
+    search_depth: u32,
+}
+
+impl From<&SolveStats> for SolveStatsJson {
+    fn from(stats: &SolveStats) -> Self {
+        Self {
+            constraint_counts: stats
+                .constraint_counts()
+                .map(|(kind, count)| (format!("{:?}", kind), count))
+                .collect(),
+            search_guesses: stats.search_guesses(),
+            search_depth: stats.search_depth(),
+        }
+    }
+}
+
+impl SolveStatsJson {
+    fn into_stats<E: serde::de::Error>(self) -> Result<SolveStats, E> {
+        let mut stats = SolveStats {
+            search_guesses: self.search_guesses,
+            search_depth: self.search_depth,
+            ..SolveStats::default()
+        };
+        for (name, count) in self.constraint_counts {
+            let kind = ConstraintKind::ALL
+                .iter()
+                .find(|kind| format!("{:?}", kind) == name)
+                .ok_or_else(|| E::custom(format!("unknown constraint kind: \"{}\"", name)))?;
+            stats.constraint_counts[kind.index()] = count;
+        }
+        Ok(stats)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum SolveResultJson<S> {
+    Unsolvable,
+    Solved {
+        solution: S,
+        used_search: bool,
+        // written for readers of the JSON, but derived from the stats when reading
+        #[serde(skip_deserializing)]
+        difficulty: Option<DifficultyJson>,
+        stats: SolveStatsJson,
+    },
+    MultipleSolutions {
+        solutions: [S; 2],
+        #[serde(skip_deserializing)]
+        differing_cells: Vec<CellJson>,
+    },
+}
+
+impl Serialize for SolveResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = match self {
+            SolveResult::Unsolvable => SolveResultJson::Unsolvable,
+            SolveResult::Solved(data) => SolveResultJson::Solved {
+                solution: &data.solution,
+                used_search: data.used_search,
+                difficulty: Some(DifficultyJson {
+                    tier: data.difficulty.tier().to_string(),
+                    score: data.difficulty.score(),
+                }),
+                stats: (&data.stats).into(),
+            },
+            SolveResult::MultipleSolutions(data) => {
+                let [a, b] = &data.solutions;
+                SolveResultJson::MultipleSolutions {
+                    solutions: [a, b],
+                    differing_cells: data
+                        .differing_cells
+                        .iter()
+                        .map(|&id| CellJson::new(a.width(), id))
+                        .collect(),
+                }
+            }
+        };
+        json.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SolveResult {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let result = match SolveResultJson::<Square<i32>>::deserialize(deserializer)? {
+            SolveResultJson::Unsolvable => SolveResult::Unsolvable,
+            SolveResultJson::Solved {
+                solution,
+                used_search,
+                stats,
+                ..
+            } => {
+                let stats = stats.into_stats()?;
+                SolveResult::Solved(SolvedData {
+                    solution,
+                    used_search,
+                    difficulty: Difficulty::from_stats(&stats),
+                    stats,
+                })
+            }
+            SolveResultJson::MultipleSolutions { solutions, .. } => {
+                if solutions[0].width() != solutions[1].width() {
+                    return Err(D::Error::custom("solutions must have the same width"));
+                }
+                SolveResult::MultipleSolutions(MultipleSolutionsData::new(solutions))
+            }
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::{Cage, Operator, Puzzle};
+    use crate::solve::{PuzzleSolver, SolveResult};
+
+    #[test]
+    fn puzzle() {
+        let puzzle = Puzzle::parse("2\nAB\nCC\n1 2 3+").unwrap();
+        let json = puzzle.to_json();
+        assert_eq!(
+            r#"{"width":2,"cages":[{"cells":[{"row":0,"col":0}],"operator":"nop","target":1},{"cells":[{"row":0,"col":1}],"operator":"nop","target":2},{"cells":[{"row":1,"col":0},{"row":1,"col":1}],"operator":"add","target":3}]}"#,
+            json
+        );
+        assert_eq!(puzzle, Puzzle::from_json(&json).unwrap());
+    }
+
+    #[test]
+    fn cage() {
+        let cage = Cage::new(vec![4, 0, 1], Operator::Add, 7).unwrap();
+        let json = serde_json::to_string(&cage).unwrap();
+        assert_eq!(r#"{"cell_ids":[0,1,4],"operator":"add","target":7}"#, json);
+        assert_eq!(cage, serde_json::from_str(&json).unwrap());
+        assert!(
+            serde_json::from_str::<Cage>(r#"{"cell_ids":[0,1],"operator":"nop","target":3}"#)
+                .is_ok()
+        );
+        assert!(
+            serde_json::from_str::<Cage>(r#"{"cell_ids":[],"operator":"add","target":3}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let puzzle = Puzzle::parse("# title: Monday\n# author: Sam\n2\nAB\nCC\n1 2 3+").unwrap();
+        let json = puzzle.to_json();
+        assert!(json.ends_with(r#""metadata":{"title":"Monday","author":"Sam"}}"#));
+        assert_eq!(puzzle, Puzzle::from_json(&json).unwrap());
+    }
+
+    #[test]
+    fn invalid_puzzle() {
+        assert!(Puzzle::from_json(
+            r#"{"width":1,"cages":[{"cells":[{"row":0,"col":1}],"operator":"nop","target":1}]}"#
+        )
+        .is_err());
+        assert!(Puzzle::from_json(
+            r#"{"width":2,"cages":[{"cells":[{"row":0,"col":0}],"operator":"nop","target":1}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn solve_result() {
+        let puzzle = Puzzle::parse("2\nAB\nCC\n1 2 3+").unwrap();
+        let result = PuzzleSolver::new(&puzzle).solve().unwrap();
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!("solved", json["status"]);
+        assert_eq!(serde_json::json!([[1, 2], [2, 1]]), json["solution"]);
+        assert_eq!("easy", json["difficulty"]["tier"]);
+        let read: SolveResult = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(json, serde_json::to_value(&read).unwrap());
+
+        let puzzle = Puzzle::parse("2\nAA\nBB\n3+ 3+").unwrap();
+        let result = PuzzleSolver::new(&puzzle).solve().unwrap();
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!("multiple_solutions", json["status"]);
+        assert_eq!(4, json["differing_cells"].as_array().unwrap().len());
+        let read: SolveResult = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(json, serde_json::to_value(&read).unwrap());
+    }
+}
@@ -21,26 +21,22 @@
 #[macro_use]
 extern crate log;
 
-#[cfg(debug_assertions)]
 use ahash::AHasher;
-#[cfg(not(debug_assertions))]
-use ahash::RandomState;
-#[cfg(debug_assertions)]
 use std::hash::BuildHasherDefault;
 
 pub mod collections;
 pub mod error;
+pub mod generate;
 pub mod image;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod puzzle;
 pub mod solve;
+pub mod text;
 
-mod generate;
 mod parse;
 
-// enable default hasher for debugging to remove randomness
-#[cfg(not(debug_assertions))]
-type DefaultBuildHasher = RandomState;
-#[cfg(debug_assertions)]
+// use a fixed hasher to remove randomness so that solving (and seeded generation) is repeatable
 type DefaultBuildHasher = BuildHasherDefault<AHasher>;
 type HashMap<K, V> = std::collections::HashMap<K, V, DefaultBuildHasher>;
 type HashSet<T> = std::collections::HashSet<T, DefaultBuildHasher>;
@@ -49,4 +45,3 @@ type LinkedHashSet<T> = linked_hash_set::LinkedHashSet<T, DefaultBuildHasher>;
 // todo documentation
 // todo lazily initialize cage solutions as needed, starting with smaller cages
 // todo prioritize and re_order constraint set by usage data
-// todo determine puzzle difficulty levels
@@ -2,6 +2,7 @@
 
 use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::mem;
 use std::str;
 
 use crate::collections::square::{SquareIndex, SquareValue};
@@ -10,6 +11,7 @@ use crate::error::{
 };
 use crate::parse::token_iterator::IndexedToken;
 use crate::puzzle::Cage;
+use crate::puzzle::Metadata;
 use crate::puzzle::Operator;
 use crate::puzzle::Puzzle;
 use token_iterator::TokenIterator;
@@ -21,11 +23,22 @@ mod token_iterator;
 
 pub type Result<T, E = ParseError> = std::result::Result<T, E>;
 
-const MAX_PUZZLE_SIZE: SquareValue = ((b'Z') - (b'A') + 1) as SquareValue;
+/// Larger puzzles are accepted by the text format but are impractical to solve
+pub(crate) const MAX_PUZZLE_SIZE: SquareValue = 64;
+
+/// Identifies the cage of a cell in the puzzle text. A puzzle uses either single letters or
+/// numbers separated by spaces, never both.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CageKey {
+    Letter(char),
+    Number(u32),
+}
 
 /// parse a `Puzzle` from a string
 pub fn parse_puzzle(s: &str) -> Result<Puzzle, ParsePuzzleError> {
+    let (metadata, start) = read_metadata(s);
     let mut s = TokenIterator::new(s);
+    s.skip_to(start);
     let size = s
         .next_skip_space()?
         .expect_token()?
@@ -42,25 +55,52 @@ pub fn parse_puzzle(s: &str) -> Result<Puzzle, ParsePuzzleError> {
     }
     let cages = cage_cells
         .into_iter()
-        .zip(cage_targets.into_iter())
+        .zip(cage_targets)
         .map(|(cells, (target, operator))| Cage::new(cells, operator, target as i32))
         .collect::<Result<_, _>>()?;
-    let puzzle = Puzzle::new(size, cages)?;
+    let mut puzzle = Puzzle::new(size, cages)?;
+    *puzzle.metadata_mut() = metadata;
     Ok(puzzle)
 }
 
+/// Reads the header of `# key: value` lines. Header lines without a key are comments. Returns the
+/// metadata and the index where the puzzle starts.
+fn read_metadata(s: &str) -> (Metadata, usize) {
+    let mut metadata = Metadata::new();
+    let mut start = 0;
+    for line in s.split_inclusive('\n') {
+        let line_trimmed = line.trim();
+        if let Some(comment) = line_trimmed.strip_prefix('#') {
+            if let Some((key, value)) = comment.split_once(':') {
+                if !key.trim().is_empty() {
+                    metadata.insert(key, value);
+                }
+            }
+        } else if !line_trimmed.is_empty() {
+            break;
+        }
+        start += line.len();
+    }
+    (metadata, start)
+}
+
 fn read_cage_cells(s: &mut TokenIterator<'_>, width: SquareValue) -> Result<Vec<Vec<SquareIndex>>> {
-    let mut cage_map: BTreeMap<char, Vec<usize>> = BTreeMap::new();
+    let mut cage_map: BTreeMap<CageKey, Vec<usize>> = BTreeMap::new();
     for cell in 0..(width as SquareIndex).pow(2) {
-        let letter = s
-            .next_skip_space()?
-            .expect_token()?
-            .map_or(InvalidCageId, Token::letter)?
-            .filter_or(InvalidCageId, char::is_uppercase)?
-            .value();
-        cage_map.entry(letter).or_default().push(cell);
+        let (i, token) = s.next_skip_space()?.expect_token()?;
+        let key = match token {
+            Token::Letter(c) if c.is_uppercase() => CageKey::Letter(c),
+            Token::Number(n) => CageKey::Number(n),
+            _ => return Err(ParseError::new(InvalidCageId, token, i)),
+        };
+        if let Some(first) = cage_map.keys().next() {
+            if mem::discriminant(first) != mem::discriminant(&key) {
+                return Err(ParseError::new(InvalidCageId, token, i));
+            }
+        }
+        cage_map.entry(key).or_default().push(cell);
     }
-    let cages = cage_map.into_iter().map(|(_id, cages)| cages).collect();
+    let cages = cage_map.into_values().collect();
     Ok(cages)
 }
 
@@ -100,18 +140,6 @@ trait IndexedTokenExt<T>: Sized + Into<(usize, T)>
 where
     T: Copy + Display,
 {
-    fn filter_or(
-        self,
-        error_type: ParsePuzzleErrorType,
-        predicate: impl FnOnce(T) -> bool,
-    ) -> Result<(usize, T)> {
-        let (index, token) = self.into();
-        if !predicate(token) {
-            return Err(ParseError::new(error_type, token, index));
-        }
-        Ok((index, token))
-    }
-
     fn map_or<U>(
         self,
         error_type: ParsePuzzleErrorType,
@@ -122,8 +150,6 @@ where
         Ok((index, n))
     }
 
-    fn index(self) -> usize;
-
     fn value(self) -> T;
 }
 
@@ -131,10 +157,6 @@ impl<T> IndexedTokenExt<T> for (usize, T)
 where
     T: Copy + Display,
 {
-    fn index(self) -> usize {
-        self.0
-    }
-
     fn value(self) -> T {
         self.1
     }
@@ -173,4 +195,85 @@ mod tests {
         let puzzle = Puzzle::new(4, cages).unwrap();
         assert_eq!(puzzle, parse_puzzle(str).unwrap());
     }
+
+    #[test]
+    fn hidden_operators() {
+        let str = "\
+        3\n\
+        AAB\
+        CDB\
+        CDD\
+        4 5 3 6";
+        let cages = [
+            Cage::new([0, 1], Operator::Nop, 4).unwrap(),
+            Cage::new([2, 5], Operator::Nop, 5).unwrap(),
+            Cage::new([3, 6], Operator::Nop, 3).unwrap(),
+            Cage::new([4, 7, 8], Operator::Nop, 6).unwrap(),
+        ]
+        .into();
+        let puzzle = Puzzle::new(3, cages).unwrap();
+        assert_eq!(puzzle, parse_puzzle(str).unwrap());
+    }
+
+    #[test]
+    fn numeric_ids() {
+        let str = "\
+        3\n\
+        0 0 1\n\
+        2 3 1\n\
+        2 3 3\n\
+        4 5 3 6";
+        assert_eq!(
+            parse_puzzle("3\nAAB\nCDB\nCDD\n4 5 3 6").unwrap(),
+            parse_puzzle(str).unwrap()
+        );
+    }
+
+    #[test]
+    fn mixed_ids() {
+        assert!(parse_puzzle("2\nA A\n1 1\n3 3").is_err());
+    }
+
+    #[test]
+    fn many_cages_round_trip() {
+        let cages = (0..36)
+            .map(|i| Cage::new([i], Operator::Nop, (i % 6 + 1) as i32).unwrap())
+            .collect();
+        let puzzle = Puzzle::new(6, cages).unwrap();
+        let str = puzzle.to_string();
+        assert!(str.starts_with("6\n 0  1  2  3  4  5\n 6  7"));
+        assert_eq!(puzzle, parse_puzzle(&str).unwrap());
+    }
+
+    #[test]
+    fn metadata() {
+        let str = "\
+        # title: Monday puzzle\n\
+        # a comment\n\
+        \n\
+        #source:  https://example.com/1 \n\
+        2\n\
+        AB\n\
+        CC\n\
+        1 2 3+";
+        let puzzle = parse_puzzle(str).unwrap();
+        let metadata: Vec<_> = puzzle.metadata().iter().collect();
+        assert_eq!(
+            vec![
+                ("title", "Monday puzzle"),
+                ("source", "https://example.com/1")
+            ],
+            metadata
+        );
+        assert!(puzzle
+            .to_string()
+            .starts_with("# title: Monday puzzle\n# source: https://example.com/1\n2\n"));
+        assert_eq!(puzzle, parse_puzzle(&puzzle.to_string()).unwrap());
+    }
+
+    #[test]
+    fn metadata_error_index() {
+        let error = parse_puzzle("# title: x\n2\nAB\nCc\n1 2 3+").unwrap_err();
+        assert_eq!("Invalid token: \"c\" at 17", error.to_string());
+    }
 }
@@ -11,13 +11,6 @@ pub enum Token {
 }
 
 impl Token {
-    pub fn letter(self) -> Option<char> {
-        match self {
-            Token::Letter(l) => Some(l),
-            _ => None,
-        }
-    }
-
     pub fn number(self) -> Option<u32> {
         match self {
             Token::Number(n) => Some(n),
@@ -20,6 +20,11 @@ impl TokenIterator<'_> {
         }
     }
 
+    /// Skips the characters before a byte index
+    pub fn skip_to(&mut self, index: usize) {
+        while self.chars.next_if(|&(i, _)| i < index).is_some() {}
+    }
+
     pub fn next_skip_space(&mut self) -> Result<Option<IndexedToken>> {
         match self.next() {
             Ok(Some((_, Token::Space))) => self.next(),
@@ -35,7 +40,7 @@ impl TokenIterator<'_> {
         let token = if c.is_whitespace() {
             loop {
                 self.chars.next().unwrap();
-                if self.chars.peek().map_or(true, |(_, c)| !c.is_whitespace()) {
+                if self.chars.peek().is_none_or(|(_, c)| !c.is_whitespace()) {
                     break;
                 }
             }
@@ -9,7 +9,7 @@ mod operator;
 ///
 /// Every cell in a KenKen puzzle belongs to a cage.
 /// Every cage has an operator and a target number.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Cage {
     /// A list of the positions of the cells in this cage
     cell_ids: Box<[CellId]>,
@@ -64,14 +64,15 @@ impl Cage {
 fn validate(cage: &Cage) -> Result<(), InvalidPuzzle> {
     match (cage.operator, cage.cell_ids().len()) {
         (_, 0) => Err(InvalidPuzzle::new("cage cell_ids must not be empty".into())),
-        (Operator::Nop, 1) => Ok(()),
-        (Operator::Nop, _) => Err(InvalidPuzzle::new(
-            "cage with multiple cells must have an operator".into(),
-        )),
+        (Operator::Nop, _) => Ok(()),
         (operator, 1) => Err(InvalidPuzzle::new(format!(
             "cage operator ({}) must have more than one cell",
             operator.symbol().unwrap()
         ))),
+        (operator, n) if !operator.allows_cage_size(n) => Err(InvalidPuzzle::new(format!(
+            "cage operator ({}) must have exactly two cells",
+            operator.symbol().unwrap()
+        ))),
         _ => Ok(()),
     }
 }
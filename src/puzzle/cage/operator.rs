@@ -6,10 +6,29 @@ pub enum Operator {
     Subtract,
     Multiply,
     Divide,
+    /// No operator is shown. A single cell cage is solved with the target. A cage with multiple
+    /// cells has a hidden operator which may be any of the other operators.
     Nop,
 }
 
 impl Operator {
+    /// The operators that may be hidden in a cage with multiple cells and no operator
+    pub const HIDDEN_OPERATORS: [Operator; 4] = [
+        Operator::Add,
+        Operator::Subtract,
+        Operator::Multiply,
+        Operator::Divide,
+    ];
+
+    /// Returns true if the operator may be used in a cage with the given number of cells
+    pub fn allows_cage_size(self, cell_count: usize) -> bool {
+        match self {
+            Operator::Add | Operator::Multiply => cell_count > 1,
+            Operator::Subtract | Operator::Divide => cell_count == 2,
+            Operator::Nop => cell_count > 0,
+        }
+    }
+
     /// Retrieve the unicode character representation of the symbol
     pub fn display_symbol(self) -> Option<char> {
         let symbol = match self {
@@ -0,0 +1,198 @@
+//! Read and write files with many puzzles
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::Path;
+
+use crate::error::ReadCollectionError;
+use crate::puzzle::Puzzle;
+
+/// The line between puzzles in a text collection
+const SEPARATOR: &str = "---";
+
+/// The file format of a puzzle collection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CollectionFormat {
+    /// Puzzles in the text format, separated by `---` lines
+    Text,
+    /// One puzzle in JSON on each line
+    #[cfg(feature = "json")]
+    JsonLines,
+}
+
+/// Reads puzzles from a collection one at a time
+pub struct CollectionReader<R> {
+    lines: Lines<R>,
+    format: CollectionFormat,
+    /// The number of puzzles read so far
+    count: usize,
+}
+
+impl CollectionReader<BufReader<File>> {
+    pub fn open(path: &Path, format: CollectionFormat) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(Self::new(BufReader::new(file), format))
+    }
+}
+
+impl<R: BufRead> CollectionReader<R> {
+    pub fn new(reader: R, format: CollectionFormat) -> Self {
+        Self {
+            lines: reader.lines(),
+            format,
+            count: 0,
+        }
+    }
+
+    /// Reads the text of the next puzzle, skipping empty entries
+    fn next_text(&mut self) -> io::Result<Option<String>> {
+        let mut text = String::new();
+        for line in &mut self.lines {
+            let line = line?;
+            match self.format {
+                CollectionFormat::Text => {
+                    if line.trim() != SEPARATOR {
+                        text.push_str(&line);
+                        text.push('\n');
+                        continue;
+                    }
+                }
+                #[cfg(feature = "json")]
+                CollectionFormat::JsonLines => text = line,
+            }
+            if !text.trim().is_empty() {
+                return Ok(Some(text));
+            }
+            text.clear();
+        }
+        Ok(Some(text).filter(|text| !text.trim().is_empty()))
+    }
+}
+
+impl<R: BufRead> Iterator for CollectionReader<R> {
+    type Item = Result<Puzzle, ReadCollectionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = match self.next_text() {
+            Ok(text) => text?,
+            Err(e) => return Some(Err(e.into())),
+        };
+        self.count += 1;
+        let index = self.count;
+        let puzzle = match self.format {
+            CollectionFormat::Text => {
+                Puzzle::parse(&text).map_err(|source| ReadCollectionError::Parse { index, source })
+            }
+            #[cfg(feature = "json")]
+            CollectionFormat::JsonLines => Puzzle::from_json(&text)
+                .map_err(|source| ReadCollectionError::Json { index, source }),
+        };
+        Some(puzzle)
+    }
+}
+
+/// Writes puzzles to a collection one at a time
+pub struct CollectionWriter<W: Write> {
+    writer: W,
+    format: CollectionFormat,
+    /// The number of puzzles written so far
+    count: usize,
+}
+
+impl CollectionWriter<BufWriter<File>> {
+    pub fn create(path: &Path, format: CollectionFormat) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(BufWriter::new(file), format))
+    }
+}
+
+impl<W: Write> CollectionWriter<W> {
+    pub fn new(writer: W, format: CollectionFormat) -> Self {
+        Self {
+            writer,
+            format,
+            count: 0,
+        }
+    }
+
+    pub fn write(&mut self, puzzle: &Puzzle) -> io::Result<()> {
+        match self.format {
+            CollectionFormat::Text => {
+                if self.count > 0 {
+                    writeln!(self.writer, "{}", SEPARATOR)?;
+                }
+                write!(self.writer, "{}", puzzle)?;
+            }
+            #[cfg(feature = "json")]
+            CollectionFormat::JsonLines => writeln!(self.writer, "{}", puzzle.to_json())?,
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// The number of puzzles written
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Flushes the output and returns the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::{CollectionFormat, CollectionReader, CollectionWriter, Puzzle};
+
+    fn puzzles() -> Vec<Puzzle> {
+        let mut a = Puzzle::parse("2\nAB\nCC\n1 2 3+").unwrap();
+        a.metadata_mut().insert("title", "A");
+        let b = Puzzle::parse("3\nAAB\nCDB\nCDD\n4+ 5+ 3 6*").unwrap();
+        vec![a, b]
+    }
+
+    fn round_trip(format: CollectionFormat) {
+        let puzzles = puzzles();
+        let mut writer = CollectionWriter::new(Vec::new(), format);
+        for puzzle in &puzzles {
+            writer.write(puzzle).unwrap();
+        }
+        assert_eq!(2, writer.count());
+        let bytes = writer.finish().unwrap();
+        let read = CollectionReader::new(&bytes[..], format)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(puzzles, read);
+    }
+
+    #[test]
+    fn text() {
+        round_trip(CollectionFormat::Text);
+        let str = "---\n2\nAB\nCC\n1 2 3+\n---\n\n---\n1\nA\n1\n";
+        let read = CollectionReader::new(str.as_bytes(), CollectionFormat::Text)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(2, read.len());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_lines() {
+        round_trip(CollectionFormat::JsonLines);
+    }
+
+    #[test]
+    fn error() {
+        let str = "1\nA\n1\n---\n2\nAB\n";
+        let mut reader = CollectionReader::new(str.as_bytes(), CollectionFormat::Text);
+        assert!(reader.next().unwrap().is_ok());
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(
+            "error reading puzzle 2 of the collection",
+            error.to_string()
+        );
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,306 @@
+//! Check a player's grid for conflicts with a puzzle
+
+use crate::collections::square::IsSquare;
+use crate::error::InvalidGrid;
+use crate::puzzle::names::{cage_name, cell_name, list, vector_name};
+use crate::puzzle::{verify_cage_values, CageId, CageRef, CellId, Operator, PlayerGrid, Puzzle};
+use crate::puzzle::{Solution, Value};
+
+/// A conflict between the entries of a `PlayerGrid` and a puzzle
+#[derive(Clone, Debug, PartialEq)]
+pub enum Conflict {
+    /// An entry is not a value from 1 to the puzzle width
+    OutOfRange { cell_id: CellId, value: Value },
+    /// A value is entered in more than one cell of a row or column
+    Duplicate { value: Value, cell_ids: Vec<CellId> },
+    /// The entries of a cage cannot be completed to make the cage target
+    ImpossibleCage { cage_id: CageId },
+    /// An entry is different from the solution of the puzzle
+    WrongEntry { cell_id: CellId, value: Value },
+}
+
+impl Conflict {
+    /// The cells involved in the conflict
+    pub fn cell_ids(&self, puzzle: &Puzzle) -> Vec<CellId> {
+        match self {
+            &Conflict::OutOfRange { cell_id, .. } | &Conflict::WrongEntry { cell_id, .. } => {
+                vec![cell_id]
+            }
+            Conflict::Duplicate { cell_ids, .. } => cell_ids.clone(),
+            &Conflict::ImpossibleCage { cage_id } => puzzle.cage(cage_id).cell_ids().to_vec(),
+        }
+    }
+
+    /// Describes the conflict in plain English
+    pub fn describe(&self, puzzle: &Puzzle) -> String {
+        match self {
+            &Conflict::OutOfRange { cell_id, value } => format!(
+                "{} is {}, which is not a value from 1 to {}",
+                cell_name(puzzle, cell_id),
+                value,
+                puzzle.width()
+            ),
+            Conflict::Duplicate { value, cell_ids } => {
+                let cells: Vec<String> = cell_ids.iter().map(|&id| cell_name(puzzle, id)).collect();
+                let vector = match cell_ids[..] {
+                    [a, b, ..] => puzzle.shared_vector(a, b),
+                    _ => None,
+                };
+                match vector {
+                    Some(vector) => {
+                        format!("{} has {} in {}", vector_name(vector), value, list(&cells))
+                    }
+                    // `check_grid` only reports duplicates within a row or column
+                    None => format!("{} is entered in {}", value, list(&cells)),
+                }
+            }
+            &Conflict::ImpossibleCage { cage_id } => {
+                let mut description = cage_name(puzzle, cage_id);
+                description[..1].make_ascii_uppercase();
+                format!("{} cannot be completed with these entries", description)
+            }
+            &Conflict::WrongEntry { cell_id, value } => format!(
+                "{} is not {} in the solution",
+                cell_name(puzzle, cell_id),
+                value
+            ),
+        }
+    }
+}
+
+impl Puzzle {
+    /// Checks the entries of a grid, which may have blank cells, against the rules of the puzzle.
+    /// Returns every conflict that is found, or an error if the grid does not have the width of
+    /// the puzzle.
+    pub fn check_grid(&self, grid: &PlayerGrid) -> Result<Vec<Conflict>, InvalidGrid> {
+        if grid.width() != self.width() {
+            return Err(InvalidGrid::new(format!(
+                "the grid width is {} but the puzzle width is {}",
+                grid.width(),
+                self.width()
+            )));
+        }
+        let width = self.width() as Value;
+        let mut conflicts: Vec<Conflict> = grid
+            .entries()
+            .iter()
+            .enumerate()
+            .filter_map(|(cell_id, &value)| Some((cell_id, value?)))
+            .filter(|&(_, value)| !(1..=width).contains(&value))
+            .map(|(cell_id, value)| Conflict::OutOfRange { cell_id, value })
+            .collect();
+        for vector in self.vectors() {
+            let mut entries: Vec<(Value, CellId)> = self
+                .vector(vector)
+                .indices()
+                .filter_map(|cell_id| Some((grid.entry(cell_id)?, cell_id)))
+                .collect();
+            entries.sort_unstable();
+            for group in entries.chunk_by(|a, b| a.0 == b.0) {
+                if group.len() > 1 {
+                    conflicts.push(Conflict::Duplicate {
+                        value: group[0].0,
+                        cell_ids: group.iter().map(|&(_, cell_id)| cell_id).collect(),
+                    });
+                }
+            }
+        }
+        for cage in self.cages() {
+            let entries: Vec<Option<Value>> =
+                cage.cell_ids().iter().map(|&id| grid.entry(id)).collect();
+            let checked = entries.iter().any(Option::is_some)
+                && entries.iter().flatten().all(|v| (1..=width).contains(v));
+            if checked && !self.is_cage_possible(cage, grid, entries) {
+                conflicts.push(Conflict::ImpossibleCage { cage_id: cage.id() });
+            }
+        }
+        Ok(conflicts)
+    }
+
+    /// Same as `check_grid`, and also finds entries that are different from the solution
+    pub fn check_grid_with_solution(
+        &self,
+        grid: &PlayerGrid,
+        solution: &Solution,
+    ) -> Result<Vec<Conflict>, InvalidGrid> {
+        if solution.width() != self.width() {
+            return Err(InvalidGrid::new(format!(
+                "the solution width is {} but the puzzle width is {}",
+                solution.width(),
+                self.width()
+            )));
+        }
+        let mut conflicts = self.check_grid(grid)?;
+        let width = self.width() as Value;
+        conflicts.extend(
+            grid.entries()
+                .iter()
+                .enumerate()
+                .filter_map(|(cell_id, &value)| Some((cell_id, value?)))
+                .filter(|&(cell_id, value)| {
+                    (1..=width).contains(&value) && value != solution[cell_id]
+                })
+                .map(|(cell_id, value)| Conflict::WrongEntry { cell_id, value }),
+        );
+        Ok(conflicts)
+    }
+
+    /// Returns true if the blank cells of a cage can be filled to make the cage target. A value
+    /// in a blank cell must not be entered elsewhere in its row or column.
+    fn is_cage_possible(
+        &self,
+        cage: CageRef<'_>,
+        grid: &PlayerGrid,
+        mut values: Vec<Option<Value>>,
+    ) -> bool {
+        let cell_ids = cage.cell_ids();
+        let blank: Vec<usize> = (0..values.len()).filter(|&i| values[i].is_none()).collect();
+        let allowed = |i: usize, value: Value, values: &[Option<Value>]| {
+            let cell = self.cell(cell_ids[i]);
+            // not entered in the same row or column
+            let in_vector = cell.vectors().iter().any(|&vector| {
+                self.vector(vector)
+                    .indices()
+                    .filter(|id| !cell_ids.contains(id))
+                    .any(|id| grid.entry(id) == Some(value))
+            });
+            // not in the same row or column within the cage
+            let in_cage = (0..values.len()).any(|j| {
+                j != i
+                    && values[j] == Some(value)
+                    && self.shared_vector(cell_ids[i], cell_ids[j]).is_some()
+            });
+            !in_vector && !in_cage
+        };
+        self.fill_cage(cage, &blank, &mut values, &allowed)
+    }
+
+    fn fill_cage(
+        &self,
+        cage: CageRef<'_>,
+        blank: &[usize],
+        values: &mut Vec<Option<Value>>,
+        allowed: &impl Fn(usize, Value, &[Option<Value>]) -> bool,
+    ) -> bool {
+        let (&i, rest) = match blank.split_first() {
+            None => {
+                let values: Vec<Value> = values.iter().map(|v| v.unwrap()).collect();
+                return verify_cage_values(cage.operator(), cage.target(), &values);
+            }
+            Some(split) => split,
+        };
+        for value in 1..=self.width() as Value {
+            if !allowed(i, value, values) {
+                continue;
+            }
+            values[i] = Some(value);
+            if partial_values_ok(cage, values) && self.fill_cage(cage, rest, values, allowed) {
+                return true;
+            }
+        }
+        values[i] = None;
+        false
+    }
+}
+
+/// Returns false if the values filled so far already exceed the target of an add or multiply cage
+fn partial_values_ok(cage: CageRef<'_>, values: &[Option<Value>]) -> bool {
+    let values = values.iter().flatten();
+    match cage.operator() {
+        Operator::Add => values.sum::<Value>() <= cage.target(),
+        Operator::Multiply => cage.target() % values.product::<Value>() == 0,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::{Conflict, PlayerGrid};
+    use crate::solve::tests::{puzzle, solution};
+
+    #[test]
+    fn no_conflicts() {
+        let puzzle = puzzle();
+        assert!(puzzle.check_grid(&PlayerGrid::new(4)).unwrap().is_empty());
+        let mut grid = PlayerGrid::new(4);
+        grid.set_entry(0, Some(4)).set_entry(2, Some(3));
+        assert!(puzzle.check_grid(&grid).unwrap().is_empty());
+    }
+
+    #[test]
+    fn conflicts() {
+        let puzzle = puzzle();
+        let mut grid = PlayerGrid::new(4);
+        grid.set_entry(0, Some(1))
+            .set_entry(1, Some(1))
+            .set_entry(2, Some(1))
+            .set_entry(5, Some(7))
+            .set_entry(13, Some(4))
+            .set_entry(15, Some(2));
+        let conflicts = puzzle.check_grid(&grid).unwrap();
+        assert_eq!(
+            "Row 1 has 1 in R1C1, R1C2 and R1C3",
+            conflicts[1].describe(&puzzle)
+        );
+        assert_eq!(
+            "The 8* cage at R1C1 cannot be completed with these entries",
+            conflicts[2].describe(&puzzle)
+        );
+        assert_eq!(
+            vec![
+                Conflict::OutOfRange {
+                    cell_id: 5,
+                    value: 7
+                },
+                Conflict::Duplicate {
+                    value: 1,
+                    cell_ids: vec![0, 1, 2],
+                },
+                // the 8* cage would need 8 in R2C1
+                Conflict::ImpossibleCage { cage_id: 0 },
+                // the 5+ cage needs 1 in R4C3, which is already in the column
+                Conflict::ImpossibleCage { cage_id: 6 },
+                // the single cell cage of 1
+                Conflict::ImpossibleCage { cage_id: 7 },
+            ],
+            conflicts
+        );
+    }
+
+    #[test]
+    fn duplicate_outside_vector() {
+        let puzzle = puzzle();
+        let conflict = Conflict::Duplicate {
+            value: 1,
+            cell_ids: vec![0, 5],
+        };
+        assert_eq!("1 is entered in R1C1 and R2C2", conflict.describe(&puzzle));
+        let conflict = Conflict::Duplicate {
+            value: 1,
+            cell_ids: vec![0],
+        };
+        assert_eq!("1 is entered in R1C1", conflict.describe(&puzzle));
+    }
+
+    #[test]
+    fn wrong_entry() {
+        let puzzle = puzzle();
+        let solution = solution(&puzzle);
+        let mut grid = PlayerGrid::new(4);
+        grid.set_entry(0, Some(2)).set_entry(2, Some(3));
+        assert!(puzzle.check_grid(&grid).unwrap().is_empty());
+        assert_eq!(
+            vec![Conflict::WrongEntry {
+                cell_id: 2,
+                value: 3
+            }],
+            puzzle.check_grid_with_solution(&grid, &solution).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_grid() {
+        let puzzle = puzzle();
+        assert!(puzzle.check_grid(&PlayerGrid::new(3)).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+/// Information about a puzzle, like its title, author, date or source, as keys and values in the
+/// order they were added
+///
+/// In the text format, metadata is a header of `# key: value` lines before the puzzle width.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    entries: Vec<(String, String)>,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a key, replacing the value if the key exists or adding the key at the
+    /// end. Surrounding whitespace is removed from the key and the value.
+    ///
+    /// Panics if the key is empty or contains `:`, or if the key or value contains a line break.
+    pub fn insert(&mut self, key: &str, value: &str) {
+        if let Err(msg) = self.try_insert(key, value) {
+            panic!("{}", msg);
+        }
+    }
+
+    pub(crate) fn try_insert(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let (key, value) = (key.trim(), value.trim());
+        if key.is_empty() || key.contains([':', '\n', '\r']) {
+            return Err(format!("invalid metadata key: \"{}\"", key));
+        }
+        if value.contains(['\n', '\r']) {
+            return Err(format!("invalid metadata value: \"{}\"", value));
+        }
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.into(),
+            None => self.entries.push((key.into(), value.into())),
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -11,29 +11,42 @@ use crate::collections::square::{
     Coord, IsSquare, Square, SquareCellRef, SquareIndex, SquareValue, SquareVector,
 };
 use crate::error::{InvalidPuzzle, ParsePuzzleError, PuzzleFromFileError};
-use crate::generate::generate_untested_puzzle;
+use crate::generate::{GeneratorConfig, PuzzleGenerator};
 use crate::parse::parse_puzzle;
-use crate::solve::ValueSet;
+use crate::solve::{DifficultyTier, ValueSet};
 
 pub use self::cage::{Cage, Operator};
+pub use self::collection::{CollectionFormat, CollectionReader, CollectionWriter};
+pub use self::conflict::Conflict;
+pub use self::metadata::Metadata;
+pub use self::player_grid::PlayerGrid;
 
 mod cage;
+mod collection;
+mod conflict;
+mod metadata;
+pub(crate) mod names;
+mod player_grid;
 
 pub type CageId = SquareIndex;
 pub type CellId = SquareIndex;
 pub type Value = i32;
 pub type Solution = Square<Value>;
 
+/// The number of cages that can be identified by a single letter in the puzzle text
+const MAX_LETTER_CAGES: usize = 26;
+
 pub(crate) type CellRef<'a> = SquareCellRef<'a, Puzzle>;
 
 /// An unsolved KenKen puzzle
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Puzzle {
     /// the width and height of the puzzle
     width: SquareValue,
     /// contains all cages in the puzzle
     cages: Box<[Cage]>,
     cage_id_map: Square<CageId>,
+    metadata: Metadata,
 }
 
 impl Puzzle {
@@ -45,6 +58,7 @@ impl Puzzle {
             width,
             cages,
             cage_id_map,
+            metadata: Metadata::new(),
         };
         Ok(puzzle)
     }
@@ -55,8 +69,19 @@ impl Puzzle {
         Ok(puzzle)
     }
 
+    /// Generates a puzzle with a single solution
+    pub fn generate(width: SquareValue) -> Puzzle {
+        PuzzleGenerator::new(GeneratorConfig::default()).generate(width)
+    }
+
     pub fn generate_untested(width: SquareValue) -> Puzzle {
-        generate_untested_puzzle(width)
+        PuzzleGenerator::new(GeneratorConfig::default()).generate_untested(width)
+    }
+
+    /// Generates a puzzle with a single solution and the given difficulty tier, or `None` if one
+    /// is not found after `MAX_DIFFICULTY_ATTEMPTS` attempts
+    pub fn generate_with_difficulty(width: SquareValue, tier: DifficultyTier) -> Option<Puzzle> {
+        PuzzleGenerator::new(GeneratorConfig::default()).generate_with_difficulty(width, tier)
     }
 
     pub fn parse(str: &str) -> Result<Self, ParsePuzzleError> {
@@ -101,25 +126,7 @@ impl Puzzle {
             .iter()
             .map(|&i| solution[i])
             .collect::<Vec<_>>();
-        match cage.operator() {
-            Operator::Add => values.iter().sum::<i32>() == cage.target(),
-            Operator::Subtract => {
-                let mut values: [_; 2] = values[..].try_into().unwrap();
-                values.sort_unstable();
-                values[1] - values[0] == cage.target()
-            }
-            Operator::Multiply => values.iter().product::<i32>() == cage.target(),
-            Operator::Divide => {
-                let mut values: [_; 2] = values[..].try_into().unwrap();
-                values.sort_unstable();
-                let [a, b] = values;
-                b % a == 0 && b / a == cage.target()
-            }
-            Operator::Nop => {
-                let [v]: [_; 1] = values[..].try_into().unwrap();
-                v == cage.target()
-            }
-        }
+        verify_cage_values(cage.operator(), cage.target(), &values)
     }
 
     fn verify_vector<'a>(&'a self, vector: SquareVector<'a, Square<i32>>) -> bool {
@@ -132,6 +139,41 @@ impl Puzzle {
     pub fn width(&self) -> SquareValue {
         self.width
     }
+
+    /// Information about the puzzle, like its title or source
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+}
+
+/// Checks that the values of a cage produce the target with the operator
+fn verify_cage_values(operator: Operator, target: Value, values: &[Value]) -> bool {
+    match operator {
+        Operator::Add => values.iter().sum::<i32>() == target,
+        Operator::Subtract => {
+            let mut values: [_; 2] = values.try_into().unwrap();
+            values.sort_unstable();
+            values[1] - values[0] == target
+        }
+        Operator::Multiply => values.iter().product::<i32>() == target,
+        Operator::Divide => {
+            let mut values: [_; 2] = values.try_into().unwrap();
+            values.sort_unstable();
+            let [a, b] = values;
+            b % a == 0 && b / a == target
+        }
+        Operator::Nop => match values {
+            [v] => *v == target,
+            _ => Operator::HIDDEN_OPERATORS
+                .iter()
+                .filter(|operator| operator.allows_cage_size(values.len()))
+                .any(|&operator| verify_cage_values(operator, target, values)),
+        },
+    }
 }
 
 /// Create a square of values where each value represents the index of the cage
@@ -157,13 +199,31 @@ fn cage_id_map(width: SquareValue, cages: &[Cage]) -> Result<Square<usize>, Inva
     Ok(cage_map)
 }
 
+/// Writes the puzzle in the text format read by `Puzzle::parse`. Cage IDs are the indices of the
+/// cages, which are ordered by their first cell. Parsing the text gives back an equal puzzle if
+/// the puzzle is at most 64 wide and every cage target is positive, which is true of generated
+/// and parsed puzzles. Other puzzles can be created with `Puzzle::new` but are rejected by the
+/// parser.
 impl Display for Puzzle {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in self.metadata.iter() {
+            writeln!(f, "# {}: {}", key, value)?;
+        }
         writeln!(f, "{}", self.width)?;
+        // cages are lettered A to Z when there are few enough, and numbered from 0 otherwise
+        let letters = self.cages.len() <= MAX_LETTER_CAGES;
+        let id_width = self.cages.len().saturating_sub(1).to_string().len();
         for i in 0..self.width {
             for j in 0..self.width {
-                let byte = b'A' + self.cage_id_map[Coord::new(j, i)] as u8;
-                write!(f, "{}", byte as char)?;
+                let cage_id = self.cage_id_map[Coord::new(j, i)];
+                if letters {
+                    write!(f, "{}", (b'A' + cage_id as u8) as char)?;
+                } else {
+                    if j > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{:>1$}", cage_id, id_width)?;
+                }
             }
             writeln!(f)?;
         }
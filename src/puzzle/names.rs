@@ -0,0 +1,55 @@
+//! Plain-English names of the parts of a puzzle
+
+use std::fmt::Write;
+
+use crate::collections::square::{Dimension, IsSquare, Vector};
+use crate::puzzle::{CageId, CellId, Puzzle};
+
+/// The name of a cell, like "R1C2"
+pub(crate) fn cell_name(puzzle: &Puzzle, cell_id: CellId) -> String {
+    let coord = puzzle.cell(cell_id).coord();
+    format!("R{}C{}", coord.row() + 1, coord.col() + 1)
+}
+
+/// The name of a cage, like "the 6+ cage at R1C2"
+pub(crate) fn cage_name(puzzle: &Puzzle, cage_id: CageId) -> String {
+    let cage = puzzle.cage(cage_id);
+    let mut name = format!("the {}", cage.target());
+    if let Some(symbol) = cage.operator().symbol() {
+        name.push(symbol);
+    }
+    write!(name, " cage at {}", cell_name(puzzle, cage.cell_ids()[0])).unwrap();
+    name
+}
+
+/// The name of a vector, like "Row 1"
+pub(crate) fn vector_name(vector: Vector) -> String {
+    let label = match vector.dimension {
+        Dimension::Row => "Row",
+        Dimension::Col => "Column",
+    };
+    format!("{} {}", label, vector.index + 1)
+}
+
+/// Lists items in plain English, like "1, 2 and 3"
+pub(crate) fn list(items: &[impl ToString]) -> String {
+    let mut items: Vec<String> = items.iter().map(ToString::to_string).collect();
+    match items.pop() {
+        None => String::new(),
+        Some(last) if items.is_empty() => last,
+        Some(last) => format!("{} and {}", items.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::names::list;
+
+    #[test]
+    fn list_items() {
+        assert_eq!("", list(&Vec::<i32>::new()));
+        assert_eq!("1", list(&[1]));
+        assert_eq!("1 and 2", list(&[1, 2]));
+        assert_eq!("1, 2 and 3", list(&[1, 2, 3]));
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::fmt::Display;
+
+use crate::collections::square::SquareValue;
+use crate::error::ParsePlayerGridError;
+use crate::puzzle::{CellId, Value};
+
+/// A player's progress on a puzzle: the values entered in cells and the pencil marks of other
+/// cells. Cells are indexed by `CellId`.
+///
+/// Values are not checked, so a grid may hold mistakes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerGrid {
+    width: SquareValue,
+    entries: Vec<Option<Value>>,
+    pencil_marks: Vec<Vec<Value>>,
+}
+
+impl PlayerGrid {
+    /// Creates a blank grid
+    pub fn new(width: SquareValue) -> Self {
+        let len = (width as usize).pow(2);
+        Self {
+            width,
+            entries: vec![None; len],
+            pencil_marks: vec![Vec::new(); len],
+        }
+    }
+
+    /// Parses the entries of a grid, with one line for each row. The cells of a row are
+    /// separated by whitespace and a blank cell is `.`. For example:
+    ///
+    /// ```text
+    /// 2 . 1
+    /// . . .
+    /// 3 2 .
+    /// ```
+    pub fn parse(str: &str) -> Result<Self, ParsePlayerGridError> {
+        let rows: Vec<Vec<&str>> = str
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|row| !row.is_empty())
+            .collect();
+        let width = rows.len();
+        if width == 0 {
+            return Err(ParsePlayerGridError::new("the grid is empty".into()));
+        }
+        let mut grid = Self::new(width as SquareValue);
+        for (row, tokens) in rows.iter().enumerate() {
+            if tokens.len() != width {
+                return Err(ParsePlayerGridError::new(format!(
+                    "row {} has {} cells but the grid has {} rows",
+                    row + 1,
+                    tokens.len(),
+                    width
+                )));
+            }
+            for (col, &token) in tokens.iter().enumerate() {
+                if token == "." {
+                    continue;
+                }
+                let value = token.parse().map_err(|_| {
+                    ParsePlayerGridError::new(format!("invalid value: \"{}\"", token))
+                })?;
+                grid.set_entry(row * width + col, Some(value));
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn width(&self) -> SquareValue {
+        self.width
+    }
+
+    /// The value entered in a cell
+    pub fn entry(&self, cell_id: CellId) -> Option<Value> {
+        self.entries[cell_id]
+    }
+
+    pub fn entries(&self) -> &[Option<Value>] {
+        &self.entries
+    }
+
+    pub fn set_entry(&mut self, cell_id: CellId, value: Option<Value>) -> &mut Self {
+        self.entries[cell_id] = value;
+        self
+    }
+
+    /// The candidate values marked in a cell. No marks means any value is possible.
+    pub fn pencil_marks(&self, cell_id: CellId) -> &[Value] {
+        &self.pencil_marks[cell_id]
+    }
+
+    pub fn set_pencil_marks(&mut self, cell_id: CellId, values: &[Value]) -> &mut Self {
+        self.pencil_marks[cell_id] = values.to_vec();
+        self
+    }
+}
+
+impl Display for PlayerGrid {
+    /// Writes the entries of the grid in the format read by `PlayerGrid::parse`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.entries.chunks(self.width as usize) {
+            let tokens: Vec<String> = row
+                .iter()
+                .map(|entry| entry.map_or_else(|| ".".to_string(), |value| value.to_string()))
+                .collect();
+            writeln!(f, "{}", tokens.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::PlayerGrid;
+
+    #[test]
+    fn parse() {
+        let str = "2 . 1\n. . .\n3 2 .\n";
+        let grid = PlayerGrid::parse(str).unwrap();
+        assert_eq!(3, grid.width());
+        assert_eq!(Some(2), grid.entry(0));
+        assert_eq!(None, grid.entry(1));
+        assert_eq!(Some(2), grid.entry(7));
+        assert_eq!(str, grid.to_string());
+    }
+
+    #[test]
+    fn parse_error() {
+        assert!(PlayerGrid::parse("").is_err());
+        assert!(PlayerGrid::parse("1 .\n2").is_err());
+        assert!(PlayerGrid::parse("1 x\n. .").is_err());
+    }
+}
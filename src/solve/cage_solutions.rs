@@ -108,13 +108,13 @@ impl CageSolutionsSet {
                 .insert(id);
         }
 
-        for (cage_id, cage_data) in data.into_iter().enumerate() {
+        for (cage_id, cage_data) in data.iter().enumerate() {
             if let Some(CageData {
                 removed_solution_ids,
                 solved_cells,
             }) = cage_data
             {
-                self[cage_id].apply_changes(&removed_solution_ids, &solved_cells);
+                self[cage_id].apply_changes(removed_solution_ids, solved_cells);
             }
         }
     }
@@ -157,12 +157,26 @@ impl CageSolutions {
             .collect();
         let index_map = Self::build_index_map(&cell_ids);
 
-        let solutions = match cage.operator() {
-            Operator::Add => Self::init_add(puzzle, cage_id, cell_variables),
-            Operator::Multiply => Self::init_multiply(puzzle, cage_id, cell_variables),
-            Operator::Subtract => Self::init_subtract(puzzle, cage_id, cell_variables),
-            Operator::Divide => Self::init_divide(puzzle, cage_id, cell_variables),
-            Operator::Nop => Vec::new(),
+        let solutions = if cage.operator() == Operator::Nop && cage.cell_count() > 1 {
+            // the operator is hidden, so use the solutions for every operator that fits the cage
+            let mut solutions: Vec<_> = Operator::HIDDEN_OPERATORS
+                .iter()
+                .filter(|operator| operator.allows_cage_size(cage.cell_count()))
+                .flat_map(|&operator| {
+                    Self::init_operator(puzzle, cage_id, operator, cell_variables)
+                })
+                .collect();
+            solutions.sort_unstable();
+            solutions.dedup();
+            solutions
+        } else if cage.operator() == Operator::Nop {
+            // a single cell cage has the target, unless the cell is already solved
+            match cell_ids.len() {
+                0 => Vec::new(),
+                _ => vec![vec![cage.target()]],
+            }
+        } else {
+            Self::init_operator(puzzle, cage_id, cage.operator(), cell_variables)
         };
 
         debug!("cage at {:?} solutions: {:?}", cage.coord(), &solutions);
@@ -174,6 +188,21 @@ impl CageSolutions {
         }
     }
 
+    fn init_operator(
+        puzzle: &Puzzle,
+        cage_id: CageId,
+        operator: Operator,
+        cell_variables: &[&CellVariable],
+    ) -> Vec<Vec<i32>> {
+        match operator {
+            Operator::Add => Self::init_add(puzzle, cage_id, cell_variables),
+            Operator::Multiply => Self::init_multiply(puzzle, cage_id, cell_variables),
+            Operator::Subtract => Self::init_subtract(puzzle, cage_id, cell_variables),
+            Operator::Divide => Self::init_divide(puzzle, cage_id, cell_variables),
+            Operator::Nop => Vec::new(),
+        }
+    }
+
     fn clear(&mut self) {
         self.cell_ids.clear();
         self.index_map.clear();
@@ -207,7 +236,7 @@ impl CageSolutions {
                 .enumerate()
                 .filter(|&(_, id)| solved_cells.contains(id))
                 .map(|(i, _)| i)
-                .collect_into(Vec::with_capacity(solved_cells.len()));
+                .collect_to(Vec::with_capacity(solved_cells.len()));
 
             for solution in &mut self.solutions {
                 solution.remove_indices_copy(&remove_indices);
@@ -291,6 +320,9 @@ impl CageSolutions {
                 }
             }
         }
+        if cage.target() % solved_product != 0 {
+            return Vec::new();
+        }
         let remain_product = cage.target() / solved_product;
         let mut solution = vec![0; indices.len()];
         let mut solutions = Vec::new();
@@ -356,7 +388,7 @@ impl CageSolutions {
             let known_val = cell_variables[solved_pos].solved().unwrap();
             let domain = cell_variables[(solved_pos + 1) % 2].unsolved().unwrap();
             let n = known_val / cage.target();
-            if n > 0 && domain.contains(n) {
+            if n > 0 && known_val % cage.target() == 0 && domain.contains(n) {
                 solutions.push(vec![n; 1]);
             }
             let m = known_val * cage.target();
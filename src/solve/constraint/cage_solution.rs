@@ -3,6 +3,7 @@ use crate::collections::square::Square;
 use crate::puzzle::{CageId, Puzzle};
 use crate::solve::cage_solutions::CageSolutions;
 use crate::solve::markup::{PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::CellVariable;
 use crate::LinkedHashSet;
 
@@ -54,6 +55,7 @@ impl<'a> Constraint for CageSolutionConstraint<'a> {
                 for (i, &value) in solution.iter().enumerate() {
                     changes.cells.solve(cell_ids[i], value);
                 }
+                changes.reasons.push(Reason::CageSolution { cage_id });
                 return true;
             }
         }
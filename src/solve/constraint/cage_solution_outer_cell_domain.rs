@@ -7,6 +7,7 @@ use crate::collections::iterator_ext::IteratorExt;
 use crate::collections::square::{IsSquare, Square, Vector};
 use crate::puzzle::{CageId, CellId, Puzzle, Value};
 use crate::solve::markup::{CellChange, PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::CellVariable;
 use crate::{HashSet, LinkedHashSet};
 
@@ -128,6 +129,11 @@ impl CageSolutionOuterCellDomainConstraint<'_> {
                     cell.coord()
                 );
                 changes.remove_cage_solution(cage.id(), solution_index);
+                changes.reasons.push(Reason::CageSolutionOuterCellDomain {
+                    cage_id,
+                    cell_id,
+                    vector,
+                });
                 count += 1;
                 break;
             }
@@ -3,6 +3,7 @@ use crate::collections::square::IsSquare;
 use crate::collections::square::{Square, Vector};
 use crate::puzzle::{CageId, CellId, CellRef, Puzzle, Value};
 use crate::solve::markup::{PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::CellVariable;
 use crate::{HashMap, HashSet, LinkedHashSet};
 
@@ -109,13 +110,26 @@ impl CageVectorValueConstraint<'_> {
         let mut count = 0;
 
         // mark domain values for removal
+        let mut removed_values = Vec::new();
         for n in values {
+            let before = count;
             for &pos in &remove_from {
                 if markup.cells()[pos].unsolved_and_contains(n) {
                     change.cells.remove_domain_value(pos, n);
                     count += 1;
                 }
             }
+            if count > before {
+                removed_values.push(n);
+            }
+        }
+        if count > 0 {
+            removed_values.sort_unstable();
+            change.reasons.push(Reason::CageVectorValue {
+                cage_id,
+                vector,
+                values: removed_values,
+            });
         }
         count
     }
@@ -152,7 +166,7 @@ impl CageVectorValueConstraint<'_> {
             .filter(|n| {
                 self.known_vector_vals
                     .get(&vector)
-                    .map_or(true, |values| !values.contains(n))
+                    .is_none_or(|values| !values.contains(n))
             })
             .collect();
 
@@ -1,9 +1,10 @@
 use super::Constraint;
 use crate::collections::square::{IsSquare, Square};
 use crate::puzzle::Puzzle;
-use crate::puzzle::{CageId, CageRef, Operator};
+use crate::puzzle::{CageId, CageRef};
 use crate::solve::cage_solutions::CageSolutions;
 use crate::solve::markup::{PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::CellVariable;
 use crate::solve::ValueSet;
 use crate::LinkedHashSet;
@@ -21,7 +22,7 @@ impl<'a> CellCageSolutionConstraint<'a> {
             puzzle,
             dirty_cages: puzzle
                 .cages()
-                .filter(|cage| cage.operator() != Operator::Nop)
+                .filter(|cage| cage.cell_count() > 1)
                 .map(CageRef::id)
                 .collect(),
         }
@@ -49,8 +50,9 @@ impl<'a> Constraint for CellCageSolutionConstraint<'a> {
     ) -> bool {
         while let Some(cage_id) = self.dirty_cages.pop_front() {
             let cage_solutions = &markup.cage_solutions().unwrap()[cage_id];
-            let count = enforce_cage(self.puzzle, &markup.cells(), cage_solutions, changes);
+            let count = enforce_cage(self.puzzle, markup.cells(), cage_solutions, changes);
             if count > 0 {
+                changes.reasons.push(Reason::CellCageSolution { cage_id });
                 return true;
             }
         }
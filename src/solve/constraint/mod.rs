@@ -12,7 +12,7 @@ use crate::solve::constraint::vector_value_domain::VectorValueDomainConstraint;
 use crate::solve::markup::PuzzleMarkup;
 use crate::solve::CellVariable;
 
-pub(crate) use self::unary_constraints::apply_unary_constraints;
+pub(crate) use self::unary_constraints::{apply_cage_unary_constraints, apply_unary_constraints};
 
 mod cage_solution;
 mod cage_solution_outer_cell_domain;
@@ -43,9 +43,10 @@ pub(crate) trait Constraint {
 }
 
 /// Defines `ConstraintList` which combines all the individual `Constraint`s and implements
-/// `Constraint` with static dispatch.
+/// `Constraint` with static dispatch. Also defines `ConstraintKind` with one variant for each
+/// `Constraint`.
 macro_rules! constraint_list {
-    ($($name:ident,)*) => {
+    ($($(#[$meta:meta])* $kind:ident: $name:ident,)*) => {
         #[derive(Clone)]
         #[allow(non_snake_case)]
         pub(crate) struct ConstraintList<'a> {
@@ -58,6 +59,20 @@ macro_rules! constraint_list {
                     $($name: $name::new(puzzle),)*
                 }
             }
+
+            /// Same as `enforce_partial` but returns the kind of constraint that found changes
+            pub fn enforce_partial_kind(
+                &mut self,
+                markup: &PuzzleMarkup<'_>,
+                changes: &mut PuzzleMarkupChanges,
+            ) -> Option<ConstraintKind> {
+                $(
+                    if self.$name.enforce_partial(markup, changes) {
+                        return Some(ConstraintKind::$kind);
+                    }
+                )*
+                None
+            }
         }
 
         impl Constraint for ConstraintList<'_> {
@@ -74,30 +89,46 @@ macro_rules! constraint_list {
                 markup: &PuzzleMarkup<'_>,
                 changes: &mut PuzzleMarkupChanges,
             ) -> bool {
-                $(self.$name.enforce_partial(markup, changes))||*
+                self.enforce_partial_kind(markup, changes).is_some()
+            }
+        }
+
+        /// The kinds of constraints used to solve a puzzle, in the order they are checked
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        pub enum ConstraintKind {
+            $($(#[$meta])* $kind,)*
+        }
+
+        impl ConstraintKind {
+            /// All constraint kinds, in the order they are checked
+            pub const ALL: &'static [ConstraintKind] = &[$(ConstraintKind::$kind,)*];
+
+            /// The position of this constraint kind in `ConstraintKind::ALL`
+            pub fn index(self) -> usize {
+                self as usize
             }
         }
     };
 }
 
 constraint_list! {
-    // when a cell is solved, remove the value from other cells in the same vector
-    VectorSolvedCellConstraint,
-    // if one cage solution remains for a cage, solve the cage
-    CageSolutionConstraint,
-    // if a vector has only one cell with a given value, solve the cell
-    VectorValueDomainConstraint,
-    // If no cage solutions have a value in a cell's domain,
-    // remove the cell domain value
-    CellCageSolutionConstraint,
-    // If all cage solutions for a cage have a value in a vector,
-    // remove the value from other cells in the vector
-    CageVectorValueConstraint,
-    // Find a set of cells in a vector that must contain a set of values
-    VectorPreemptiveSetConstraint,
-    // If, within a vector, a value is known to be in a certain cage,
-    // remove cage solutions without the value in the vector
-    VectorValueCageConstraint,
-    // Remove cage solutions that conflict with a cell's entire domain outside of the cage
-    CageSolutionOuterCellDomainConstraint,
+    /// When a cell is solved, remove the value from other cells in the same vector
+    VectorSolvedCell: VectorSolvedCellConstraint,
+    /// If one cage solution remains for a cage, solve the cage
+    CageSolution: CageSolutionConstraint,
+    /// If a vector has only one cell with a given value, solve the cell
+    VectorValueDomain: VectorValueDomainConstraint,
+    /// If no cage solutions have a value in a cell's domain,
+    /// remove the cell domain value
+    CellCageSolution: CellCageSolutionConstraint,
+    /// If all cage solutions for a cage have a value in a vector,
+    /// remove the value from other cells in the vector
+    CageVectorValue: CageVectorValueConstraint,
+    /// Find a set of cells in a vector that must contain a set of values
+    VectorPreemptiveSet: VectorPreemptiveSetConstraint,
+    /// If, within a vector, a value is known to be in a certain cage,
+    /// remove cage solutions without the value in the vector
+    VectorValueCage: VectorValueCageConstraint,
+    /// Remove cage solutions that conflict with a cell's entire domain outside of the cage
+    CageSolutionOuterCellDomain: CageSolutionOuterCellDomainConstraint,
 }
@@ -11,15 +11,37 @@ use crate::collections::iterator_ext::IteratorExt;
 use crate::collections::square::{IsSquare, SquareValue};
 use crate::puzzle::{CageRef, CellId, Operator};
 use crate::puzzle::{Puzzle, Value};
-use crate::solve::markup::CellChanges;
+use crate::solve::markup::{CellChanges, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::ValueSet;
 
 /// Applies all unary constraints to cell domains. Returns a list of all affected cells by index.
-pub(crate) fn apply_unary_constraints(puzzle: &Puzzle, changes: &mut CellChanges) {
+pub(crate) fn apply_unary_constraints(puzzle: &Puzzle, changes: &mut PuzzleMarkupChanges) {
     debug!("Reducing cell domains by cage-specific info");
 
     for cage in puzzle.cages() {
-        reduce_cage(puzzle, cage, changes);
+        apply_cage_unary_constraints(puzzle, cage, changes);
+    }
+}
+
+/// Applies the unary constraints of one cage to its cell domains
+pub(crate) fn apply_cage_unary_constraints(
+    puzzle: &Puzzle,
+    cage: CageRef<'_>,
+    changes: &mut PuzzleMarkupChanges,
+) {
+    reduce_cage(puzzle, cage, &mut changes.cells);
+    // each cell is in one cage, so changes to the cage's cells are from this cage
+    if cage
+        .cell_ids()
+        .iter()
+        .any(|&id| changes.cells.get(id).is_some())
+    {
+        let cage_id = cage.id();
+        changes.reasons.push(match cage.operator() {
+            Operator::Nop if cage.cell_count() == 1 => Reason::SingleCellCage { cage_id },
+            _ => Reason::CageArithmetic { cage_id },
+        });
     }
 }
 
@@ -29,8 +51,9 @@ fn reduce_cage(puzzle: &Puzzle, cage: CageRef<'_>, changes: &mut CellChanges) {
         Operator::Multiply => reduce_cage_multiply(puzzle, cage, changes),
         Operator::Subtract => reduce_cage_subtract(puzzle, cage, changes),
         Operator::Divide => reduce_cage_divide(puzzle, cage, changes),
+        // the operator is hidden; cage solutions are used instead
+        Operator::Nop if cage.cell_count() > 1 => {}
         Operator::Nop => {
-            debug_assert_eq!(1, cage.cell_count());
             let cell = cage.cell(0);
             debug!("solving single cell cage at {:?}", cage.cell(0).coord());
             changes.solve(cell.id(), cage.target());
@@ -54,7 +77,7 @@ fn reduce_cage_add(puzzle: &Puzzle, cage: CageRef<'_>, changes: &mut CellChanges
             .iter()
             .copied()
             .filter(|&i| i != cell)
-            .collect_into(Vec::with_capacity(cage.cell_count() - 1));
+            .collect_to(Vec::with_capacity(cage.cell_count() - 1));
         let (other_min, other_max) = cells_add_min_max(puzzle, &other_cells);
         let min = cage.target() - other_max;
         let max = cage.target() - other_min;
@@ -138,7 +161,7 @@ fn cells_add_min_max(puzzle: &Puzzle, cells: &[CellId]) -> (i32, i32) {
         // simple case
         return (1, puzzle.width() as i32);
     }
-    let group_sequence = cell_group_sizes(puzzle, &cells);
+    let group_sequence = cell_group_sizes(puzzle, cells);
     group_sequence_min_max(&group_sequence, puzzle.width())
 }
 
@@ -188,7 +211,8 @@ fn group_sequence_min_max(group_sequence: &[usize], puzzle_width: SquareValue) -
 mod tests {
     use crate::puzzle::Puzzle;
     use crate::solve::constraint::apply_unary_constraints;
-    use crate::solve::markup::CellChanges;
+    use crate::solve::markup::{CellChanges, PuzzleMarkupChanges};
+    use crate::solve::reason::Reason;
 
     #[test]
     fn test() {
@@ -201,7 +225,7 @@ mod tests {
             8* 5+ 4+ 1 8+ 9+ 4 2",
         )
         .unwrap();
-        let mut changes = CellChanges::new();
+        let mut changes = PuzzleMarkupChanges::default();
         apply_unary_constraints(&puzzle, &mut changes);
         let mut expected = CellChanges::new();
         expected.remove_domain_value(0, 3);
@@ -217,6 +241,10 @@ mod tests {
         expected.solve(10, 2);
         expected.solve(11, 4);
         expected.solve(15, 1);
-        assert_eq!(changes, expected);
+        assert_eq!(changes.cells, expected);
+        assert_eq!(6, changes.reasons.len());
+        assert!(changes
+            .reasons
+            .contains(&Reason::SingleCellCage { cage_id: 7 }));
     }
 }
@@ -6,6 +6,7 @@ use crate::collections::square::Square;
 use crate::collections::square::{IsSquare, Vector};
 use crate::puzzle::{CellId, Puzzle};
 use crate::solve::markup::{PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::CellVariable;
 use crate::solve::ValueSet;
 use crate::LinkedHashSet;
@@ -46,7 +47,7 @@ impl<'a> Constraint for VectorPreemptiveSetConstraint<'a> {
         changes: &mut PuzzleMarkupChanges,
     ) -> bool {
         while let Some(vector) = self.dirty_vecs.front().copied() {
-            let count = enforce_vector(&markup.cells(), vector, changes);
+            let count = enforce_vector(markup.cells(), vector, changes);
             if count == 0 {
                 self.dirty_vecs.pop_front();
             } else {
@@ -158,5 +159,12 @@ fn found_preemptive_set(
             }
         }
     }
+    if count > 0 {
+        changes.reasons.push(Reason::VectorPreemptiveSet {
+            vector,
+            cell_ids: cells.to_vec(),
+            values: values.iter().collect(),
+        });
+    }
     count
 }
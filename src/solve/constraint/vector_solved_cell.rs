@@ -2,6 +2,7 @@ use super::Constraint;
 use crate::collections::square::{IsSquare, Square};
 use crate::puzzle::{CellId, Puzzle};
 use crate::solve::markup::{PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::CellVariable;
 
 /// If a cell is solved in a vector, other cells in that vector must not have the same value.
@@ -39,8 +40,11 @@ impl<'a> Constraint for VectorSolvedCellConstraint<'a> {
     ) -> bool {
         while let Some(cell_id) = self.solved_cells.pop() {
             let value = markup.cells()[cell_id].solved().unwrap();
-            let count = self.enforce_solved_cell(&markup.cells(), cell_id, value, changes);
+            let count = self.enforce_solved_cell(markup.cells(), cell_id, value, changes);
             if count > 0 {
+                changes
+                    .reasons
+                    .push(Reason::VectorSolvedCell { cell_id, value });
                 return true;
             }
         }
@@ -7,6 +7,7 @@ use crate::collections::square::{IsSquare, Square, Vector};
 use crate::puzzle::{CellRef, Puzzle, Value};
 use crate::solve::constraint::Constraint;
 use crate::solve::markup::{CellChange, PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::CellVariable;
 use crate::LinkedHashSet;
 
@@ -121,6 +122,11 @@ impl VectorValueCageConstraint<'_> {
             }
         }
         if count > 0 {
+            changes.reasons.push(Reason::VectorValueCage {
+                vector,
+                value,
+                cage_id: cage,
+            });
             debug!(
                 "Removed {} cage solutions for cage at {:?} where cage does not have {} in {:?}",
                 count,
@@ -5,6 +5,7 @@ use crate::collections::range_set::RangeSet;
 use crate::collections::square::{EmptySquare, IsSquare, Square, SquareValue, Vector};
 use crate::puzzle::{CellId, Puzzle, Value};
 use crate::solve::markup::{CellChange, PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::CellVariable;
 use crate::LinkedHashSet;
 
@@ -47,6 +48,11 @@ impl<'a> VectorValueDomainConstraint<'a> {
             puzzle.cell(cell_id).coord()
         );
         change.cells.solve(cell_id, n);
+        change.reasons.push(Reason::VectorValueDomain {
+            vector,
+            value: n,
+            cell_id,
+        });
         self.data.remove_cell_value(cell_id, n);
         true
     }
@@ -0,0 +1,70 @@
+//! A single logical deduction and the rule used to make it
+
+use crate::puzzle::{CageId, CellId, Puzzle, Value};
+use crate::solve::markup::{CellChange, PuzzleMarkupChanges};
+use crate::solve::ConstraintKind;
+
+/// A single logical deduction made while solving
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deduction {
+    /// The rule used to make the deduction
+    pub rule: DeductionRule,
+    /// A plain-English explanation, like "Row 3: 4 can only go in C3"
+    pub description: String,
+    /// Every cell changed by the deduction, in order
+    pub cells: Vec<CellId>,
+    /// Cells that are solved by the deduction
+    pub solved: Vec<(CellId, Value)>,
+    /// Values that are ruled out for unsolved cells
+    pub eliminated: Vec<(CellId, Vec<Value>)>,
+    /// Cages with cage solutions that are ruled out, in order
+    pub cages: Vec<CageId>,
+}
+
+/// The rules used to make a deduction, from simplest to most complex
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeductionRule {
+    /// A cage with one cell has the value of its target
+    SingleCellCage,
+    /// The operator and target of a cage rule out values in its cells
+    CageArithmetic,
+    /// One of the constraints used by the solver
+    Constraint(ConstraintKind),
+}
+
+impl Deduction {
+    pub(crate) fn new(puzzle: &Puzzle, rule: DeductionRule, changes: &PuzzleMarkupChanges) -> Self {
+        let description = changes
+            .reasons
+            .iter()
+            .map(|reason| reason.describe(puzzle))
+            .collect::<Vec<_>>()
+            .join(". ");
+        let mut solved = Vec::new();
+        let mut eliminated = Vec::new();
+        for (&cell_id, change) in &changes.cells {
+            match change {
+                &CellChange::Solution(value) => solved.push((cell_id, value)),
+                CellChange::DomainRemovals(values) => {
+                    let mut values: Vec<Value> = values.iter().copied().collect();
+                    values.sort_unstable();
+                    eliminated.push((cell_id, values));
+                }
+            }
+        }
+        solved.sort_unstable();
+        eliminated.sort_unstable();
+        let mut cells: Vec<CellId> = changes.cells.keys().copied().collect();
+        cells.sort_unstable();
+        let mut cages: Vec<CageId> = changes.cage_solution_removals.keys().copied().collect();
+        cages.sort_unstable();
+        Self {
+            rule,
+            description,
+            cells,
+            solved,
+            eliminated,
+            cages,
+        }
+    }
+}
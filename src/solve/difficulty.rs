@@ -0,0 +1,215 @@
+//! Rate the difficulty of a puzzle based on how it was solved
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::error::ParseDifficultyTierError;
+use crate::solve::ConstraintKind;
+
+/// Score added for each guess made in backtracking search
+const SEARCH_GUESS_SCORE: u32 = 20;
+/// Score added for each level of backtracking search depth
+const SEARCH_DEPTH_SCORE: u32 = 50;
+
+/// Statistics collected while solving a puzzle
+#[derive(Clone, Debug, Default)]
+pub struct SolveStats {
+    /// The number of times each kind of constraint contributed changes, by `ConstraintKind::index`
+    pub(crate) constraint_counts: [u32; ConstraintKind::ALL.len()],
+    /// The number of guesses made in backtracking search
+    pub(crate) search_guesses: u32,
+    /// The maximum depth of backtracking search
+    pub(crate) search_depth: u32,
+}
+
+impl SolveStats {
+    /// The number of times a kind of constraint contributed changes to the puzzle markup
+    pub fn constraint_count(&self, kind: ConstraintKind) -> u32 {
+        self.constraint_counts[kind.index()]
+    }
+
+    /// The number of times each kind of constraint contributed changes to the puzzle markup
+    pub fn constraint_counts(&self) -> impl Iterator<Item = (ConstraintKind, u32)> + '_ {
+        ConstraintKind::ALL
+            .iter()
+            .map(move |&kind| (kind, self.constraint_count(kind)))
+    }
+
+    /// The number of guesses made in backtracking search
+    pub fn search_guesses(&self) -> u32 {
+        self.search_guesses
+    }
+
+    /// The maximum depth of backtracking search, or 0 if search was not used
+    pub fn search_depth(&self) -> u32 {
+        self.search_depth
+    }
+
+    pub(crate) fn record_constraint(&mut self, kind: ConstraintKind) {
+        self.constraint_counts[kind.index()] += 1;
+    }
+
+    pub(crate) fn record_guess(&mut self, depth: u32) {
+        self.search_guesses += 1;
+        self.search_depth = self.search_depth.max(depth);
+    }
+}
+
+/// The difficulty of a puzzle, measured by solving it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Difficulty {
+    tier: DifficultyTier,
+    score: u32,
+}
+
+impl Difficulty {
+    /// Rates the difficulty of a puzzle from the statistics of solving it.
+    ///
+    /// The tier is determined by the most advanced constraint that was needed and whether
+    /// backtracking search was needed. The score accumulates a weight for every time a constraint
+    /// was used, plus extra for backtracking search. Scores may be used to sort puzzles of the
+    /// same width.
+    pub fn from_stats(stats: &SolveStats) -> Self {
+        let tier = if stats.search_guesses > 0 {
+            DifficultyTier::Expert
+        } else {
+            stats
+                .constraint_counts()
+                .filter(|&(_, count)| count > 0)
+                .map(|(kind, _)| constraint_tier(kind))
+                .max()
+                .unwrap_or(DifficultyTier::Easy)
+        };
+        let constraint_score: u32 = stats
+            .constraint_counts()
+            .map(|(kind, count)| constraint_weight(kind) * count)
+            .sum();
+        let score = constraint_score
+            + stats.search_guesses * SEARCH_GUESS_SCORE
+            + stats.search_depth * SEARCH_DEPTH_SCORE;
+        Self { tier, score }
+    }
+
+    pub fn tier(self) -> DifficultyTier {
+        self.tier
+    }
+
+    pub fn score(self) -> u32 {
+        self.score
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (score: {})", self.tier, self.score)
+    }
+}
+
+/// A general level of difficulty
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DifficultyTier {
+    /// Solved with simple elimination of values in rows, columns and cages
+    Easy,
+    /// Requires reasoning about the possible solutions of cages in a row or column
+    Medium,
+    /// Requires advanced reasoning about sets of cells and cage solutions
+    Hard,
+    /// Requires guessing (backtracking search)
+    Expert,
+}
+
+impl DifficultyTier {
+    pub const ALL: [DifficultyTier; 4] = [
+        DifficultyTier::Easy,
+        DifficultyTier::Medium,
+        DifficultyTier::Hard,
+        DifficultyTier::Expert,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DifficultyTier::Easy => "easy",
+            DifficultyTier::Medium => "medium",
+            DifficultyTier::Hard => "hard",
+            DifficultyTier::Expert => "expert",
+        }
+    }
+}
+
+impl Display for DifficultyTier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DifficultyTier {
+    type Err = ParseDifficultyTierError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|tier| tier.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseDifficultyTierError::new(s.into()))
+    }
+}
+
+/// The lowest tier of a puzzle that requires a kind of constraint
+fn constraint_tier(kind: ConstraintKind) -> DifficultyTier {
+    match kind {
+        ConstraintKind::VectorSolvedCell
+        | ConstraintKind::CageSolution
+        | ConstraintKind::VectorValueDomain
+        | ConstraintKind::CellCageSolution => DifficultyTier::Easy,
+        ConstraintKind::CageVectorValue | ConstraintKind::VectorPreemptiveSet => {
+            DifficultyTier::Medium
+        }
+        ConstraintKind::VectorValueCage | ConstraintKind::CageSolutionOuterCellDomain => {
+            DifficultyTier::Hard
+        }
+    }
+}
+
+/// The score added each time a kind of constraint is used
+fn constraint_weight(kind: ConstraintKind) -> u32 {
+    match kind {
+        ConstraintKind::VectorSolvedCell => 1,
+        ConstraintKind::CageSolution => 1,
+        ConstraintKind::VectorValueDomain => 2,
+        ConstraintKind::CellCageSolution => 2,
+        ConstraintKind::CageVectorValue => 4,
+        ConstraintKind::VectorPreemptiveSet => 5,
+        ConstraintKind::VectorValueCage => 8,
+        ConstraintKind::CageSolutionOuterCellDomain => 8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tier_from_str() {
+        assert_eq!(DifficultyTier::Hard, "hard".parse().unwrap());
+        assert_eq!(DifficultyTier::Expert, "Expert".parse().unwrap());
+        assert!("impossible".parse::<DifficultyTier>().is_err());
+    }
+
+    #[test]
+    fn from_stats() {
+        let mut stats = SolveStats::default();
+        assert_eq!(DifficultyTier::Easy, Difficulty::from_stats(&stats).tier());
+        stats.record_constraint(ConstraintKind::VectorSolvedCell);
+        stats.record_constraint(ConstraintKind::VectorPreemptiveSet);
+        let difficulty = Difficulty::from_stats(&stats);
+        assert_eq!(DifficultyTier::Medium, difficulty.tier());
+        assert_eq!(6, difficulty.score());
+        stats.record_guess(2);
+        let difficulty = Difficulty::from_stats(&stats);
+        assert_eq!(DifficultyTier::Expert, difficulty.tier());
+        assert_eq!(
+            6 + SEARCH_GUESS_SCORE + 2 * SEARCH_DEPTH_SCORE,
+            difficulty.score()
+        );
+    }
+}
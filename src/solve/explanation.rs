@@ -0,0 +1,118 @@
+//! A numbered, plain-English walkthrough of the steps of a solve
+
+use std::fmt::Write;
+
+use crate::puzzle::names::{cell_name, list};
+use crate::puzzle::{CellId, Puzzle};
+use crate::solve::markup::{CellChange, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
+use crate::solve::SolveResult;
+
+pub(crate) struct ExplanationWriter<'a> {
+    puzzle: &'a Puzzle,
+    text: String,
+    step: u32,
+}
+
+impl<'a> ExplanationWriter<'a> {
+    pub fn new(puzzle: &'a Puzzle) -> Self {
+        Self {
+            puzzle,
+            text: String::new(),
+            step: 0,
+        }
+    }
+
+    /// Writes a numbered step for each reason of the changes, followed by their effects.
+    /// Steps are indented by the search depth.
+    pub fn write_step(&mut self, changes: &PuzzleMarkupChanges, depth: usize) {
+        let (scoped, unscoped): (Vec<&Reason>, Vec<&Reason>) = changes
+            .reasons
+            .iter()
+            .partition(|reason| reason.cage_scope().is_some());
+        for reason in scoped {
+            let cage = self.puzzle.cage(reason.cage_scope().unwrap());
+            let cells = cage.cell_ids();
+            let effects = self.effects(changes, |cell_id| cells.contains(&cell_id));
+            self.write_numbered(depth, &reason.describe(self.puzzle), &effects);
+        }
+        if !unscoped.is_empty() {
+            let description = unscoped
+                .iter()
+                .map(|reason| reason.describe(self.puzzle))
+                .collect::<Vec<_>>()
+                .join(". ");
+            let effects = match self.effects(changes, |_| true) {
+                effects if !effects.is_empty() => effects,
+                _ => self.cage_solution_effects(changes),
+            };
+            self.write_numbered(depth, &description, &effects);
+        }
+    }
+
+    /// Writes an unnumbered note
+    pub fn write_note(&mut self, note: &str, depth: usize) {
+        writeln!(self.text, "{}{}", indent(depth), note).unwrap();
+    }
+
+    /// Writes the outcome of the solve and returns the explanation
+    pub fn finish(mut self, result: &SolveResult) -> String {
+        let outcome = match result {
+            SolveResult::Solved(_) => "The puzzle is solved.",
+            SolveResult::Unsolvable => "The puzzle has no solution.",
+            SolveResult::MultipleSolutions(_) => "The puzzle has more than one solution.",
+        };
+        self.write_note(outcome, 0);
+        self.text
+    }
+
+    fn write_numbered(&mut self, depth: usize, description: &str, effects: &str) {
+        self.step += 1;
+        let prefix = format!("{}{}. ", indent(depth), self.step);
+        writeln!(self.text, "{}{}.", prefix, description).unwrap();
+        if !effects.is_empty() {
+            // align the effects with the description
+            let indent = " ".repeat(prefix.chars().count());
+            writeln!(self.text, "{}{}", indent, effects).unwrap();
+        }
+    }
+
+    /// Describes the changes to cells that match a filter, like "R1C1 is 2. R1C2 cannot be 1 or 3."
+    fn effects(&self, changes: &PuzzleMarkupChanges, filter: impl Fn(CellId) -> bool) -> String {
+        let mut cells: Vec<(CellId, &CellChange)> = changes
+            .cells
+            .iter()
+            .map(|(&id, change)| (id, change))
+            .filter(|&(id, _)| filter(id))
+            .collect();
+        cells.sort_unstable_by_key(|&(id, _)| id);
+        cells
+            .into_iter()
+            .map(|(id, change)| match change {
+                CellChange::Solution(value) => {
+                    format!("{} is {}.", cell_name(self.puzzle, id), value)
+                }
+                CellChange::DomainRemovals(values) => {
+                    let mut values: Vec<_> = values.iter().copied().collect();
+                    values.sort_unstable();
+                    let values = list(&values).replace(" and ", " or ");
+                    format!("{} cannot be {}.", cell_name(self.puzzle, id), values)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn cage_solution_effects(&self, changes: &PuzzleMarkupChanges) -> String {
+        let count: usize = changes.cage_solution_removals.values().map(Vec::len).sum();
+        match count {
+            0 => String::new(),
+            1 => "1 cage solution is ruled out.".to_string(),
+            count => format!("{} cage solutions are ruled out.", count),
+        }
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
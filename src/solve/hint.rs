@@ -0,0 +1,152 @@
+//! Find the next logical deduction for a puzzle that is partially filled by a player
+
+use crate::error::HintError;
+use crate::puzzle::{PlayerGrid, Puzzle, Value};
+use crate::solve::deduction::Deduction;
+use crate::solve::markup::PuzzleMarkupChanges;
+use crate::solve::SolverState;
+
+/// Finds the simplest deduction that changes a cell, from the entries and pencil marks of a grid.
+/// Returns `None` if the grid is complete or if no deduction can be made without guessing.
+pub(crate) fn find_hint(
+    puzzle: &Puzzle,
+    grid: &PlayerGrid,
+) -> Result<Option<Deduction>, HintError> {
+    let mut state = SolverState::new(puzzle);
+    state.apply_changes(grid_changes(puzzle, grid)?)?;
+    // changes to cage solutions alone are not shown to the player, so continue until cells change
+    while let Some(deduction) = state.step()? {
+        if !deduction.cells.is_empty() {
+            return Ok(Some(deduction));
+        }
+    }
+    Ok(None)
+}
+
+/// The changes from a blank markup to the state of the grid
+fn grid_changes(puzzle: &Puzzle, grid: &PlayerGrid) -> Result<PuzzleMarkupChanges, HintError> {
+    if grid.width() != puzzle.width() {
+        return Err(HintError::InvalidGrid {
+            msg: format!(
+                "the grid width is {} but the puzzle width is {}",
+                grid.width(),
+                puzzle.width()
+            ),
+        });
+    }
+    let width = puzzle.width() as Value;
+    let check_value = |value: Value| {
+        if (1..=width).contains(&value) {
+            Ok(())
+        } else {
+            Err(HintError::InvalidGrid {
+                msg: format!("{} is not a value from 1 to {}", value, width),
+            })
+        }
+    };
+    let mut changes = PuzzleMarkupChanges::default();
+    for cell_id in 0..puzzle.cell_count() {
+        if let Some(value) = grid.entry(cell_id) {
+            check_value(value)?;
+            changes.cells.solve(cell_id, value);
+            continue;
+        }
+        let marks = grid.pencil_marks(cell_id);
+        if marks.is_empty() {
+            continue;
+        }
+        for &value in marks {
+            check_value(value)?;
+        }
+        for value in (1..=width).filter(|value| !marks.contains(value)) {
+            changes.cells.remove_domain_value(cell_id, value);
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::HintError;
+    use crate::puzzle::PlayerGrid;
+    use crate::solve::hint::find_hint;
+    use crate::solve::tests::{puzzle, solution};
+    use crate::solve::ConstraintKind;
+    use crate::solve::DeductionRule;
+
+    #[test]
+    fn first_cage() {
+        let puzzle = puzzle();
+        let hint = find_hint(&puzzle, &PlayerGrid::new(4)).unwrap().unwrap();
+        // the first cage with new information
+        assert_eq!(DeductionRule::CageArithmetic, hint.rule);
+        assert_eq!(vec![0, 1, 4], hint.cells);
+        assert!(hint.solved.is_empty());
+    }
+
+    #[test]
+    fn skips_known_deductions() {
+        let puzzle = puzzle();
+        let mut grid = PlayerGrid::new(4);
+        grid.set_pencil_marks(0, &[1, 2, 4])
+            .set_pencil_marks(1, &[1, 2, 4])
+            .set_pencil_marks(4, &[1, 2, 4]);
+        let hint = find_hint(&puzzle, &grid).unwrap().unwrap();
+        assert_eq!(DeductionRule::CageArithmetic, hint.rule);
+        assert_eq!(vec![2, 3], hint.cells);
+        assert_eq!(vec![(2, vec![2, 4]), (3, vec![2, 4])], hint.eliminated);
+    }
+
+    #[test]
+    fn constraint() {
+        let puzzle = puzzle();
+        let mut grid = PlayerGrid::new(4);
+        // single cell cages are entered and unary constraints are marked
+        grid.set_entry(10, Some(2))
+            .set_entry(11, Some(4))
+            .set_entry(15, Some(1));
+        for &cell in &[0, 1, 4] {
+            grid.set_pencil_marks(cell, &[1, 2, 4]);
+        }
+        for &cell in &[2, 3] {
+            grid.set_pencil_marks(cell, &[1, 3]);
+        }
+        for &cell in &[5, 6, 7] {
+            grid.set_pencil_marks(cell, &[2, 3, 4]);
+        }
+        let hint = find_hint(&puzzle, &grid).unwrap().unwrap();
+        assert_eq!(
+            DeductionRule::Constraint(ConstraintKind::VectorSolvedCell),
+            hint.rule
+        );
+        assert!(hint.description.contains(", so no other cell in row "));
+    }
+
+    #[test]
+    fn contradiction() {
+        let puzzle = puzzle();
+        let mut grid = PlayerGrid::new(4);
+        grid.set_entry(0, Some(1)).set_entry(1, Some(1));
+        assert!(matches!(
+            find_hint(&puzzle, &grid),
+            Err(HintError::Contradiction(_))
+        ));
+        let mut grid = PlayerGrid::new(4);
+        grid.set_entry(0, Some(5));
+        assert!(matches!(
+            find_hint(&puzzle, &grid),
+            Err(HintError::InvalidGrid { .. })
+        ));
+    }
+
+    #[test]
+    fn completed() {
+        let puzzle = puzzle();
+        let solution = solution(&puzzle);
+        let mut grid = PlayerGrid::new(4);
+        for (cell, &value) in solution.iter().enumerate() {
+            grid.set_entry(cell, Some(value));
+        }
+        assert_eq!(None, find_hint(&puzzle, &grid).unwrap());
+    }
+}
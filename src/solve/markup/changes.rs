@@ -2,18 +2,22 @@ use std::borrow::{Borrow, BorrowMut};
 use std::collections::hash_map;
 
 use crate::puzzle::{CageId, CellId, Value};
+use crate::solve::reason::Reason;
 use crate::{HashMap, HashSet};
 
 #[derive(Debug, Default, PartialEq)]
 pub(crate) struct PuzzleMarkupChanges {
     pub cells: CellChanges,
     pub cage_solution_removals: HashMap<CageId, Vec<usize>>,
+    /// Why the changes were made
+    pub reasons: Vec<Reason>,
 }
 
 impl PuzzleMarkupChanges {
     pub fn clear(&mut self) {
         self.cells.clear();
         self.cage_solution_removals.clear();
+        self.reasons.clear();
     }
 
     pub fn is_cage_solution_removed(&self, cage_id: CageId, solution_index: usize) -> bool {
@@ -38,6 +42,7 @@ pub enum CellChange {
 pub(crate) struct CellChanges(HashMap<CellId, CellChange>);
 
 impl CellChanges {
+    #[cfg(test)]
     pub fn new() -> Self {
         Self::default()
     }
@@ -55,7 +60,7 @@ impl CellChanges {
     }
 
     pub fn iter(&self) -> <&HashMap<CellId, CellChange> as IntoIterator>::IntoIter {
-        self.0.borrow().into_iter()
+        self.0.borrow().iter()
     }
 
     pub fn keys(&self) -> hash_map::Keys<'_, CellId, CellChange> {
@@ -122,11 +127,11 @@ impl<'a> IntoIterator for &'a mut CellChanges {
     type IntoIter = <&'a mut HashMap<CellId, CellChange> as IntoIterator>::IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.borrow_mut().into_iter()
+        self.0.borrow_mut().iter_mut()
     }
 }
 
-impl<'a> IntoIterator for CellChanges {
+impl IntoIterator for CellChanges {
     type Item = <HashMap<CellId, CellChange> as IntoIterator>::Item;
     type IntoIter = <HashMap<CellId, CellChange> as IntoIterator>::IntoIter;
 
@@ -1,35 +1,53 @@
 //! solve KenKen puzzles
 
+use std::fs;
+use std::mem;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use self::constraint::apply_unary_constraints;
 use self::markup::{PuzzleMarkup, PuzzleMarkupChanges};
-use crate::puzzle::{Puzzle, Solution};
+use crate::error::HintError;
+use crate::image::{GifWriter, ImageFormat, ImageTheme, DEFAULT_THEME};
+use crate::puzzle::{CellId, PlayerGrid, Puzzle, Solution};
 use crate::solve::constraint::{Constraint, ConstraintList};
-use crate::solve::search::{search_solution, SearchResult};
+use crate::solve::search::{search_solution, Search, SearchResult};
 use crate::solve::step_writer::StepWriter;
 
+pub use self::constraint::ConstraintKind;
+pub use self::deduction::{Deduction, DeductionRule};
+pub use self::difficulty::{Difficulty, DifficultyTier, SolveStats};
+pub use self::state::SolverState;
+
 pub(crate) use self::cell_variable::CellVariable;
 pub(crate) use self::value_set::ValueSet;
 
 mod cage_solutions;
 mod cell_variable;
 mod constraint;
+mod deduction;
+mod difficulty;
+mod explanation;
+mod hint;
 pub(crate) mod markup;
+pub(crate) mod reason;
 mod search;
+mod state;
 mod step_writer;
 mod value_set;
 
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(500);
+
 pub enum SolveResult {
     /// The puzzle cannot be solved - there may be an error in the puzzle
     Unsolvable,
     /// The puzzle was solved and has exactly one solution, as it should
     Solved(SolvedData),
     /// Multiple solutions were found for the puzzle - this is not a proper puzzle
-    MultipleSolutions,
+    MultipleSolutions(MultipleSolutionsData),
 }
 
 impl SolveResult {
@@ -48,11 +66,38 @@ impl SolveResult {
 pub struct SolvedData {
     pub solution: Solution,
     pub used_search: bool,
+    /// Statistics about how the puzzle was solved
+    pub stats: SolveStats,
+    /// The difficulty of the puzzle, derived from `stats`
+    pub difficulty: Difficulty,
+}
+
+pub struct MultipleSolutionsData {
+    /// Two different solutions to the puzzle
+    pub solutions: [Solution; 2],
+    /// The cells with different values in the two solutions
+    pub differing_cells: Vec<CellId>,
+}
+
+impl MultipleSolutionsData {
+    pub(crate) fn new(solutions: [Solution; 2]) -> Self {
+        let [a, b] = &solutions;
+        let differing_cells = (0..a.len()).filter(|&i| a[i] != b[i]).collect();
+        Self {
+            solutions,
+            differing_cells,
+        }
+    }
 }
 
 pub struct PuzzleSolver<'a> {
     puzzle: &'a Puzzle,
     steps_path: Option<PathBuf>,
+    step_image_format: ImageFormat,
+    step_image_theme: &'a ImageTheme,
+    explanation_path: Option<PathBuf>,
+    animation_path: Option<PathBuf>,
+    frame_delay: Duration,
 }
 
 impl<'a> PuzzleSolver<'a> {
@@ -60,6 +105,11 @@ impl<'a> PuzzleSolver<'a> {
         Self {
             puzzle,
             steps_path: None,
+            step_image_format: ImageFormat::default(),
+            step_image_theme: &DEFAULT_THEME,
+            explanation_path: None,
+            animation_path: None,
+            frame_delay: DEFAULT_FRAME_DELAY,
         }
     }
 
@@ -68,81 +118,231 @@ impl<'a> PuzzleSolver<'a> {
         self
     }
 
+    /// Sets the file format of step images, which is SVG by default
+    pub fn step_image_format(&mut self, format: ImageFormat) -> &mut Self {
+        self.step_image_format = format;
+        self
+    }
+
+    /// Sets the style of step images and animation frames
+    pub fn step_image_theme(&mut self, theme: &'a ImageTheme) -> &mut Self {
+        self.step_image_theme = theme;
+        self
+    }
+
+    /// Saves a numbered, plain-English explanation of each solution step to a text file
+    pub fn save_explanation(&mut self, path: &Path) -> &mut Self {
+        self.explanation_path = Some(path.into());
+        self
+    }
+
+    /// Saves an animated GIF with a frame for each solution step, including the steps of
+    /// backtracking search
+    pub fn save_animation(&mut self, path: &Path) -> &mut Self {
+        self.animation_path = Some(path.into());
+        self
+    }
+
+    /// Sets how long each frame of the animation is shown, which is 500ms by default
+    pub fn frame_delay(&mut self, delay: Duration) -> &mut Self {
+        self.frame_delay = delay;
+        self
+    }
+
     pub fn solve(&self) -> Result<SolveResult> {
-        let mut changes = PuzzleMarkupChanges::default();
-        apply_unary_constraints(self.puzzle, &mut changes.cells);
-        let mut markup = PuzzleMarkup::new(self.puzzle);
-        let solvable = markup.sync_changes(&mut changes);
-        debug_assert!(solvable);
         let mut step_writer = self.start_step_writer();
-        if let Some(ref mut step_writer) = step_writer {
-            step_writer.write_step(&markup, &changes.cells)?;
+        let result = self.solve_inner(&mut step_writer.as_mut())?;
+        let explanation = match step_writer {
+            Some(step_writer) => step_writer.finish(&result)?,
+            None => None,
+        };
+        if let (Some(explanation), Some(path)) = (explanation, &self.explanation_path) {
+            fs::write(path, explanation)
+                .with_context(|| format!("Error saving explanation to {}", path.display()))?;
         }
-        markup.init_cage_solutions(self.puzzle);
-        let mut constraints = ConstraintList::new(self.puzzle);
-        constraints.notify_changes(&changes, markup.cells());
-        markup.apply_changes(&changes);
-        let solution = match propagate_constraints(
-            self.puzzle,
-            &mut constraints,
-            &mut markup,
-            &mut step_writer.as_mut(),
-        )? {
+        Ok(result)
+    }
+
+    /// Solves the puzzle without saving any steps, which cannot fail
+    pub(crate) fn solve_without_steps(&self) -> SolveResult {
+        without_steps(|step_writer| self.solve_inner(step_writer))
+    }
+
+    fn solve_inner(&self, step_writer: &mut Option<&mut StepWriter<'_>>) -> Result<SolveResult> {
+        let mut stats = SolveStats::default();
+        let (markup, constraints, result) = self.propagate(&mut stats, step_writer)?;
+        let solution = match result {
             PropagateResult::Solved(solution) => Some(solution),
             PropagateResult::Unsolved => None,
             PropagateResult::Invalid => return Ok(SolveResult::Unsolvable),
         };
-        let result = if let Some(solution) = solution {
-            SolvedData {
-                solution,
-                used_search: false,
-            }
+        let used_search = solution.is_none();
+        let solution = if let Some(solution) = solution {
+            solution
         } else {
             info!("Begin backtracking");
-            let solution =
-                match search_solution(self.puzzle, markup, constraints, &mut step_writer.as_mut())?
-                {
-                    SearchResult::NoSolutions => return Ok(SolveResult::Unsolvable),
-                    SearchResult::SingleSolution(solution) => solution,
-                    SearchResult::MultipleSolutions => return Ok(SolveResult::MultipleSolutions),
-                };
-            SolvedData {
-                solution,
-                used_search: true,
+            match search_solution(self.puzzle, markup, constraints, &mut stats, step_writer)? {
+                SearchResult::NoSolutions => return Ok(SolveResult::Unsolvable),
+                SearchResult::SingleSolution(solution) => solution,
+                SearchResult::MultipleSolutions(a, b) => {
+                    let data = MultipleSolutionsData::new([a, b]);
+                    return Ok(SolveResult::MultipleSolutions(data));
+                }
             }
         };
+        let result = SolvedData {
+            solution,
+            used_search,
+            difficulty: Difficulty::from_stats(&stats),
+            stats,
+        };
         debug_assert!(self.puzzle.verify_solution(&result.solution));
         Ok(SolveResult::Solved(result))
     }
 
+    /// Returns an iterator over every solution of the puzzle. Use `Iterator::take` to limit the
+    /// number of solutions.
+    ///
+    /// Step images are not saved.
+    pub fn solutions(&self) -> Solutions<'a> {
+        let mut stats = SolveStats::default();
+        let (markup, constraints, result) =
+            without_steps(|step_writer| self.propagate(&mut stats, step_writer));
+        let state = match result {
+            PropagateResult::Solved(solution) => SolutionsState::Solved(solution),
+            PropagateResult::Unsolved => SolutionsState::Search(Search::new(markup, constraints)),
+            PropagateResult::Invalid => SolutionsState::Done,
+        };
+        Solutions {
+            puzzle: self.puzzle,
+            stats,
+            state,
+        }
+    }
+
+    /// Counts the solutions of the puzzle, stopping at `limit` if there is one
+    pub fn count_solutions(&self, limit: Option<usize>) -> usize {
+        match limit {
+            None => self.solutions().count(),
+            Some(limit) => self.solutions().take(limit).count(),
+        }
+    }
+
+    /// Finds the simplest deduction that a player can make next from the entries and pencil marks
+    /// of a grid, without solving the rest of the puzzle. Returns `None` if the grid is complete or
+    /// if no deduction can be made without guessing.
+    pub fn hint(&self, grid: &PlayerGrid) -> Result<Option<Deduction>, HintError> {
+        hint::find_hint(self.puzzle, grid)
+    }
+
+    /// Applies unary constraints and then propagates constraints until no more progress is made
+    fn propagate(
+        &self,
+        stats: &mut SolveStats,
+        step_writer: &mut Option<&mut StepWriter<'_>>,
+    ) -> Result<(PuzzleMarkup<'a>, ConstraintList<'a>, PropagateResult)> {
+        let mut changes = PuzzleMarkupChanges::default();
+        apply_unary_constraints(self.puzzle, &mut changes);
+        let mut markup = PuzzleMarkup::new(self.puzzle);
+        let solvable = markup.sync_changes(&mut changes);
+        debug_assert!(solvable);
+        if let Some(step_writer) = step_writer {
+            step_writer.write_step(&markup, &changes)?;
+        }
+        markup.init_cage_solutions(self.puzzle);
+        let mut constraints = ConstraintList::new(self.puzzle);
+        constraints.notify_changes(&changes, markup.cells());
+        markup.apply_changes(&changes);
+        let result = propagate_constraints(
+            self.puzzle,
+            &mut constraints,
+            &mut markup,
+            stats,
+            step_writer,
+        )?;
+        Ok((markup, constraints, result))
+    }
+
     fn start_step_writer(&self) -> Option<StepWriter<'_>> {
-        let path = self.steps_path.as_ref()?;
-        let step_writer = StepWriter::new(self.puzzle, path.into());
+        let explain = self.explanation_path.is_some();
+        if self.steps_path.is_none() && self.animation_path.is_none() && !explain {
+            return None;
+        }
+        let animation = self
+            .animation_path
+            .as_ref()
+            .map(|path| GifWriter::new(path, self.frame_delay));
+        let step_writer = StepWriter::new(
+            self.puzzle,
+            self.steps_path.clone(),
+            self.step_image_format,
+            self.step_image_theme,
+            animation,
+            explain,
+        );
         Some(step_writer)
     }
 }
 
+/// An iterator over every solution of a puzzle, created by `PuzzleSolver::solutions`
+pub struct Solutions<'a> {
+    puzzle: &'a Puzzle,
+    stats: SolveStats,
+    state: SolutionsState<'a>,
+}
+
+enum SolutionsState<'a> {
+    /// The puzzle was solved without search
+    Solved(Solution),
+    Search(Search<'a>),
+    Done,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match mem::replace(&mut self.state, SolutionsState::Done) {
+            SolutionsState::Solved(solution) => Some(solution),
+            SolutionsState::Search(mut search) => {
+                let solution = without_steps(|step_writer| {
+                    search.next_solution(self.puzzle, &mut self.stats, step_writer)
+                })?;
+                self.state = SolutionsState::Search(search);
+                Some(solution)
+            }
+            SolutionsState::Done => None,
+        }
+    }
+}
+
+/// Runs part of the solver with no step writer. The solver only fails when saving steps.
+fn without_steps<T>(f: impl FnOnce(&mut Option<&mut StepWriter<'_>>) -> Result<T>) -> T {
+    f(&mut None).expect("the solver failed without saving steps")
+}
+
 pub(crate) fn propagate_constraints(
     puzzle: &Puzzle,
     constraints: &mut ConstraintList<'_>,
     markup: &mut PuzzleMarkup<'_>,
+    stats: &mut SolveStats,
     step_writer: &mut Option<&mut StepWriter<'_>>,
 ) -> Result<PropagateResult> {
     let mut changes = PuzzleMarkupChanges::default();
     let mut loop_count = 0;
     loop {
-        let has_changes = constraints.enforce_partial(markup, &mut changes);
-        if !has_changes {
-            break;
-        }
+        let kind = match constraints.enforce_partial_kind(markup, &mut changes) {
+            None => break,
+            Some(kind) => kind,
+        };
+        stats.record_constraint(kind);
+        debug_assert!(!changes.reasons.is_empty(), "{:?} gave no reason", kind);
         if !markup.sync_changes(&mut changes) {
             return Ok(PropagateResult::Invalid);
         }
         debug!("Changes: {:?}", changes);
         if let Some(step_writer) = step_writer.as_mut() {
-            if !changes.cells.is_empty() {
-                step_writer.write_step(markup, &changes.cells)?;
-            }
+            step_writer.write_step(markup, &changes)?;
         }
         constraints.notify_changes(&changes, markup.cells());
         markup.apply_changes(&changes);
@@ -175,3 +375,40 @@ pub(crate) enum PropagateResult {
     Unsolved,
     Invalid,
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::puzzle::{Puzzle, Solution};
+    use crate::solve::PuzzleSolver;
+
+    /// A 4x4 puzzle used by tests, with the solution:
+    ///
+    /// ```text
+    /// 2 4 1 3
+    /// 1 3 4 2
+    /// 3 1 2 4
+    /// 4 2 3 1
+    /// ```
+    pub(crate) fn puzzle() -> Puzzle {
+        Puzzle::parse(
+            "4\n\
+            AACC\n\
+            AFFF\n\
+            EEHG\n\
+            EBBD\n\
+            8* 5+ 4+ 1 8+ 9+ 4 2",
+        )
+        .unwrap()
+    }
+
+    /// The solution of a puzzle that has a single solution
+    pub(crate) fn solution(puzzle: &Puzzle) -> Solution {
+        PuzzleSolver::new(puzzle)
+            .solve()
+            .unwrap()
+            .solved()
+            .unwrap()
+            .solution
+            .clone()
+    }
+}
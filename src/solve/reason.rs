@@ -0,0 +1,210 @@
+//! Reasons for the changes made to the puzzle markup while solving
+
+use crate::collections::square::{Dimension, IsSquare, Vector};
+use crate::puzzle::names::{cage_name, cell_name, list, vector_name};
+use crate::puzzle::{CageId, CellId, Operator, Puzzle, Value};
+
+/// The reason for a set of changes to the puzzle markup, produced by a constraint or a guess
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Reason {
+    /// A cage with one cell is solved with its target
+    SingleCellCage { cage_id: CageId },
+    /// The operator and target of a cage rule out some values in its cells
+    CageArithmetic { cage_id: CageId },
+    /// A solved cell rules out its value in the rest of its row and column
+    VectorSolvedCell { cell_id: CellId, value: Value },
+    /// One cage solution remains
+    CageSolution { cage_id: CageId },
+    /// Only one cell in a vector may have a value
+    VectorValueDomain {
+        vector: Vector,
+        value: Value,
+        cell_id: CellId,
+    },
+    /// Some cell values are not in any remaining cage solution
+    CellCageSolution { cage_id: CageId },
+    /// Every cage solution has some values in a vector
+    CageVectorValue {
+        cage_id: CageId,
+        vector: Vector,
+        values: Vec<Value>,
+    },
+    /// A set of cells in a vector must contain a set of values of the same size
+    VectorPreemptiveSet {
+        vector: Vector,
+        cell_ids: Vec<CellId>,
+        values: Vec<Value>,
+    },
+    /// A value in a vector can only be in one cage
+    VectorValueCage {
+        vector: Vector,
+        value: Value,
+        cage_id: CageId,
+    },
+    /// A cage solution would leave no value for a cell outside of the cage
+    CageSolutionOuterCellDomain {
+        cage_id: CageId,
+        cell_id: CellId,
+        vector: Vector,
+    },
+    /// A value is guessed for a cell during search
+    Guess { cell_id: CellId, value: Value },
+}
+
+impl Reason {
+    /// The cage that the changes are limited to, when several reasons are applied at once
+    pub fn cage_scope(&self) -> Option<CageId> {
+        match *self {
+            Reason::SingleCellCage { cage_id } | Reason::CageArithmetic { cage_id } => {
+                Some(cage_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Describes the reason in plain English
+    pub fn describe(&self, puzzle: &Puzzle) -> String {
+        match self {
+            &Reason::SingleCellCage { cage_id } => {
+                let cage = puzzle.cage(cage_id);
+                format!(
+                    "The single cell cage at {} must be {}",
+                    cell_name(puzzle, cage.cell_ids()[0]),
+                    cage.target()
+                )
+            }
+            &Reason::CageArithmetic { cage_id } => {
+                let cage = puzzle.cage(cage_id);
+                let rule = match cage.operator() {
+                    Operator::Add => format!("must add up to {}", cage.target()),
+                    Operator::Subtract => format!("must differ by {}", cage.target()),
+                    Operator::Multiply => format!("must be factors of {}", cage.target()),
+                    Operator::Divide => {
+                        format!("must have one value {} times the other", cage.target())
+                    }
+                    Operator::Nop => format!("must make {}", cage.target()),
+                };
+                format!("The values in {} {}", cage_name(puzzle, cage_id), rule)
+            }
+            &Reason::VectorSolvedCell { cell_id, value } => {
+                let coord = puzzle.cell(cell_id).coord();
+                format!(
+                    "{} is {}, so no other cell in row {} or column {} can be {}",
+                    cell_name(puzzle, cell_id),
+                    value,
+                    coord.row() + 1,
+                    coord.col() + 1,
+                    value
+                )
+            }
+            &Reason::CageSolution { cage_id } => {
+                format!(
+                    "Only one solution remains for {}",
+                    cage_name(puzzle, cage_id)
+                )
+            }
+            &Reason::VectorValueDomain {
+                vector,
+                value,
+                cell_id,
+            } => {
+                let coord = puzzle.cell(cell_id).coord();
+                let position = match vector.dimension {
+                    Dimension::Row => format!("C{}", coord.col() + 1),
+                    Dimension::Col => format!("R{}", coord.row() + 1),
+                };
+                format!(
+                    "{}: {} can only go in {}",
+                    vector_name(vector),
+                    value,
+                    position
+                )
+            }
+            &Reason::CellCageSolution { cage_id } => format!(
+                "Some values are not in any remaining solution of {}",
+                cage_name(puzzle, cage_id)
+            ),
+            Reason::CageVectorValue {
+                cage_id,
+                vector,
+                values,
+            } => format!(
+                "{}: every solution of {} has {}, so no other cell in {} can",
+                vector_name(*vector),
+                cage_name(puzzle, *cage_id),
+                list(values),
+                vector_name(*vector).to_lowercase()
+            ),
+            Reason::VectorPreemptiveSet {
+                vector,
+                cell_ids,
+                values,
+            } => {
+                let cells: Vec<String> = cell_ids.iter().map(|&id| cell_name(puzzle, id)).collect();
+                format!(
+                    "{}: {} must contain {}, so no other cell in {} can",
+                    vector_name(*vector),
+                    list(&cells),
+                    list(values),
+                    vector_name(*vector).to_lowercase()
+                )
+            }
+            &Reason::VectorValueCage {
+                vector,
+                value,
+                cage_id,
+            } => format!(
+                "{}: {} can only go in {}, so its solutions without {} in {} are ruled out",
+                vector_name(vector),
+                value,
+                cage_name(puzzle, cage_id),
+                value,
+                vector_name(vector).to_lowercase()
+            ),
+            &Reason::CageSolutionOuterCellDomain {
+                cage_id,
+                cell_id,
+                vector,
+            } => format!(
+                "A solution of {} would use every value left for {} in {}, so it is ruled out",
+                cage_name(puzzle, cage_id),
+                cell_name(puzzle, cell_id),
+                vector_name(vector).to_lowercase()
+            ),
+            &Reason::Guess { cell_id, value } => {
+                format!("Guess that {} is {}", cell_name(puzzle, cell_id), value)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collections::square::Vector;
+    use crate::solve::reason::Reason;
+    use crate::solve::tests::puzzle;
+
+    #[test]
+    fn describe() {
+        let puzzle = puzzle();
+        let reason = Reason::VectorValueDomain {
+            vector: Vector::row(2),
+            value: 4,
+            cell_id: 10,
+        };
+        assert_eq!("Row 3: 4 can only go in C3", reason.describe(&puzzle));
+        let reason = Reason::CageArithmetic { cage_id: 1 };
+        assert_eq!(
+            "The values in the 4+ cage at R1C3 must add up to 4",
+            reason.describe(&puzzle)
+        );
+        let reason = Reason::VectorSolvedCell {
+            cell_id: 15,
+            value: 1,
+        };
+        assert_eq!(
+            "R4C4 is 1, so no other cell in row 4 or column 4 can be 1",
+            reason.describe(&puzzle)
+        );
+    }
+}
@@ -4,26 +4,27 @@ use crate::collections::square::IsSquare;
 use crate::puzzle::{CellId, Puzzle, Solution};
 use crate::solve::constraint::{Constraint, ConstraintList};
 use crate::solve::markup::{PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
 use crate::solve::step_writer::StepWriter;
-use crate::solve::{propagate_constraints, PropagateResult};
+use crate::solve::{propagate_constraints, PropagateResult, SolveStats};
 
 pub(crate) enum SearchResult {
     NoSolutions,
     SingleSolution(Solution),
-    MultipleSolutions,
+    /// Two of the solutions that were found
+    MultipleSolutions(Solution, Solution),
 }
 
-struct SearchContext<'a, 'b> {
-    puzzle: &'a Puzzle,
-    step_writer: &'a mut Option<&'b mut StepWriter<'b>>,
-    stack: Vec<SearchStackFrame<'b>>,
+/// The state of a backtracking search, which may be resumed to find more solutions
+#[derive(Clone)]
+pub(crate) struct Search<'a> {
+    stack: Vec<SearchStackFrame<'a>>,
 }
 
 #[derive(Clone)]
 struct SearchStackFrame<'a> {
     markup: PuzzleMarkup<'a>,
     constraints: ConstraintList<'a>,
-    solved_once: bool,
     guesses: Option<Guesses>,
 }
 
@@ -33,35 +34,62 @@ struct Guesses {
     index: usize,
 }
 
+/// Searches for a solution and checks that there is not another solution
 pub(crate) fn search_solution<'a>(
     puzzle: &Puzzle,
     markup: PuzzleMarkup<'a>,
     constraints: ConstraintList<'a>,
-    step_writer: &mut Option<&'a mut StepWriter<'a>>,
+    stats: &mut SolveStats,
+    step_writer: &mut Option<&mut StepWriter<'_>>,
 ) -> Result<SearchResult> {
-    SearchContext {
-        puzzle,
-        stack: vec![SearchStackFrame {
-            markup,
-            constraints,
-            solved_once: false,
-            guesses: None,
-        }],
-        step_writer,
+    let mut search = Search::new(markup, constraints);
+    if let Some(ref mut step_writer) = step_writer {
+        step_writer.start_search_branch();
+    }
+    let result = match search.next_solution(puzzle, stats, step_writer)? {
+        None => SearchResult::NoSolutions,
+        Some(solution) => {
+            if let Some(ref mut step_writer) = step_writer {
+                step_writer.write_note(
+                    "This is a solution. Keep searching to check that it is the only one.",
+                );
+            }
+            match search.next_solution(puzzle, stats, step_writer)? {
+                None => SearchResult::SingleSolution(solution),
+                Some(solution2) => SearchResult::MultipleSolutions(solution, solution2),
+            }
+        }
+    };
+    if let Some(ref mut step_writer) = step_writer {
+        step_writer.end_search_branch();
     }
-    .search()
+    Ok(result)
 }
 
-impl SearchContext<'_, '_> {
-    fn search(&mut self) -> Result<SearchResult> {
-        debug!("Backtracking (depth={})", self.stack.len());
-        let mut solution = None;
-        if let Some(ref mut step_writer) = self.step_writer {
-            step_writer.start_search_branch();
+impl<'a> Search<'a> {
+    pub fn new(markup: PuzzleMarkup<'a>, constraints: ConstraintList<'a>) -> Self {
+        Self {
+            stack: vec![SearchStackFrame {
+                markup,
+                constraints,
+                guesses: None,
+            }],
         }
+    }
+
+    /// Continues the search until the next solution is found. Returns `None` when every
+    /// possibility has been searched.
+    pub fn next_solution(
+        &mut self,
+        puzzle: &Puzzle,
+        stats: &mut SolveStats,
+        step_writer: &mut Option<&mut StepWriter<'_>>,
+    ) -> Result<Option<Solution>> {
+        debug!("Backtracking (depth={})", self.stack.len());
         loop {
+            let depth = self.stack.len() as u32;
             let frame = match self.stack.last_mut() {
-                None => break,
+                None => return Ok(None),
                 Some(frame) => frame,
             };
             let guesses = frame.guesses.get_or_insert_with(|| {
@@ -78,23 +106,28 @@ impl SearchContext<'_, '_> {
                 Some(value) => value,
             };
             guesses.index += 1;
-            if let Some(ref mut step_writer) = self.step_writer {
+            stats.record_guess(depth);
+            if let Some(ref mut step_writer) = step_writer {
                 step_writer.next_search_branch();
             }
             debug!(
                 "Guessing with {} at {:?}, guess #: {}",
                 value,
-                self.puzzle.cell(guesses.cell_id).coord(),
+                puzzle.cell(guesses.cell_id).coord(),
                 guesses.index,
             );
             let mut changes = PuzzleMarkupChanges::default();
             changes.cells.solve(guesses.cell_id, value);
+            changes.reasons.push(Reason::Guess {
+                cell_id: guesses.cell_id,
+                value,
+            });
             if !frame.markup.sync_changes(&mut changes) {
                 debug!("Guess failed");
                 continue;
             }
-            if let Some(ref mut step_writer) = self.step_writer {
-                step_writer.write_step(&frame.markup, &changes.cells)?;
+            if let Some(ref mut step_writer) = step_writer {
+                step_writer.write_step(&frame.markup, &changes)?;
             }
             let next_frame = SearchStackFrame {
                 guesses: None,
@@ -107,33 +140,26 @@ impl SearchContext<'_, '_> {
                 .notify_changes(&changes, frame.markup.cells());
             frame.markup.apply_changes(&changes);
             match propagate_constraints(
-                self.puzzle,
+                puzzle,
                 &mut frame.constraints,
                 &mut frame.markup,
-                self.step_writer,
+                stats,
+                step_writer,
             )? {
-                PropagateResult::Solved(p_solution) => {
+                PropagateResult::Solved(solution) => {
                     self.stack.pop().unwrap();
-                    let frame = self.stack.last_mut().unwrap();
-                    if frame.solved_once {
-                        return Ok(SearchResult::MultipleSolutions);
-                    }
-                    solution = Some(p_solution);
-                    frame.solved_once = true;
+                    return Ok(Some(solution));
                 }
                 PropagateResult::Unsolved => {}
                 PropagateResult::Invalid => {
                     debug!("Guess failed");
+                    if let Some(ref mut step_writer) = step_writer {
+                        step_writer.write_note("This guess leads to a contradiction, so undo it.");
+                    }
                     self.stack.pop().unwrap();
                 }
             }
         }
-        if let Some(ref mut step_writer) = self.step_writer {
-            step_writer.end_search_branch();
-        }
-        Ok(solution.map_or(SearchResult::NoSolutions, |s| {
-            SearchResult::SingleSolution(s)
-        }))
     }
 }
 
@@ -0,0 +1,318 @@
+//! Inspect and drive the state of the solver step by step
+
+use crate::collections::square::IsSquare;
+use crate::error::{Contradiction, SolverStateError};
+use crate::puzzle::{CageId, CellId, Puzzle, Solution, Value};
+use crate::solve::constraint::{apply_cage_unary_constraints, Constraint, ConstraintList};
+use crate::solve::deduction::{Deduction, DeductionRule};
+use crate::solve::markup::{CellChange, PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::reason::Reason;
+
+/// The candidate values of every cell and the possible solutions of every cage of a puzzle, as
+/// known by the solver.
+///
+/// A new state has every value as a candidate. Values can be entered or eliminated by hand, and
+/// `step` makes one deduction at a time. Any change that leaves the puzzle without a solution
+/// returns a `Contradiction`, after which the state should not be used. `set_value` and
+/// `eliminate` return an `InvalidInput` error for a cell or value that is not part of the puzzle
+/// and leave the state unchanged. The other methods panic on a cell or cage that is not part of
+/// the puzzle, like `Puzzle::cage`.
+#[derive(Clone)]
+pub struct SolverState<'a> {
+    puzzle: &'a Puzzle,
+    markup: PuzzleMarkup<'a>,
+    constraints: ConstraintList<'a>,
+    /// True once the operators and targets of cages have nothing more to rule out
+    arithmetic_done: bool,
+}
+
+impl<'a> SolverState<'a> {
+    pub fn new(puzzle: &'a Puzzle) -> Self {
+        let mut markup = PuzzleMarkup::new(puzzle);
+        markup.init_cage_solutions(puzzle);
+        Self {
+            puzzle,
+            markup,
+            constraints: ConstraintList::new(puzzle),
+            arithmetic_done: false,
+        }
+    }
+
+    pub fn puzzle(&self) -> &'a Puzzle {
+        self.puzzle
+    }
+
+    /// The value of a cell, if it is solved
+    pub fn value(&self, cell_id: CellId) -> Option<Value> {
+        self.markup.cells()[cell_id].solved()
+    }
+
+    /// The candidate values of a cell, in order. A solved cell has one candidate.
+    pub fn candidates(&self, cell_id: CellId) -> Vec<Value> {
+        let cell = &self.markup.cells()[cell_id];
+        match cell.solved() {
+            Some(value) => vec![value],
+            None => cell.unsolved().unwrap().iter().collect(),
+        }
+    }
+
+    /// The possible solutions of a cage. Each solution has a value for every cell of the cage,
+    /// in the order of `Cage::cell_ids`.
+    pub fn cage_solutions(&self, cage_id: CageId) -> Vec<Vec<Value>> {
+        let cage = self.puzzle.cage(cage_id);
+        let cage_solutions = &self.markup.cage_solutions().unwrap()[cage_id];
+        let cage_solution = |solution: &[Value]| {
+            cage.cell_ids()
+                .iter()
+                .map(|&cell_id| match cage_solutions.index_map.get(&cell_id) {
+                    Some(&i) => solution[i],
+                    None => self.value(cell_id).unwrap(),
+                })
+                .collect()
+        };
+        if cage_solutions.cell_ids.is_empty() {
+            // every cell of the cage is solved
+            return vec![cage_solution(&[])];
+        }
+        cage_solutions
+            .solutions
+            .iter()
+            .map(|solution| cage_solution(solution))
+            .collect()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.markup.is_completed()
+    }
+
+    /// The solution, if every cell is solved
+    pub fn solution(&self) -> Option<Solution> {
+        self.markup.completed_values()
+    }
+
+    /// Solves a cell with a value
+    pub fn set_value(&mut self, cell_id: CellId, value: Value) -> Result<(), SolverStateError> {
+        self.check_input(cell_id, value)?;
+        let mut changes = PuzzleMarkupChanges::default();
+        changes.cells.solve(cell_id, value);
+        self.apply_changes(changes)?;
+        Ok(())
+    }
+
+    /// Removes a candidate value from a cell
+    pub fn eliminate(&mut self, cell_id: CellId, value: Value) -> Result<(), SolverStateError> {
+        self.check_input(cell_id, value)?;
+        let mut changes = PuzzleMarkupChanges::default();
+        changes.cells.remove_domain_value(cell_id, value);
+        self.apply_changes(changes)?;
+        Ok(())
+    }
+
+    /// Makes the simplest deduction that is available. Returns `None` if the puzzle is solved or
+    /// if no deduction can be made without guessing.
+    ///
+    /// The operators and targets of cages are used first, one cage at a time, followed by the
+    /// constraints of the solver in the order of `ConstraintKind::ALL`. A deduction may only rule
+    /// out cage solutions, without changing any cells.
+    pub fn step(&mut self) -> Result<Option<Deduction>, Contradiction> {
+        if self.markup.is_completed() {
+            return Ok(None);
+        }
+        if !self.arithmetic_done {
+            for cage in self.puzzle.cages() {
+                let mut changes = PuzzleMarkupChanges::default();
+                apply_cage_unary_constraints(self.puzzle, cage, &mut changes);
+                let changes = self.apply_changes(changes)?;
+                if changes.cells.is_empty() {
+                    continue;
+                }
+                let rule = match changes.reasons[0] {
+                    Reason::SingleCellCage { .. } => DeductionRule::SingleCellCage,
+                    _ => DeductionRule::CageArithmetic,
+                };
+                return Ok(Some(Deduction::new(self.puzzle, rule, &changes)));
+            }
+            self.arithmetic_done = true;
+        }
+        let mut changes = PuzzleMarkupChanges::default();
+        let kind = match self
+            .constraints
+            .enforce_partial_kind(&self.markup, &mut changes)
+        {
+            None => return Ok(None),
+            Some(kind) => kind,
+        };
+        let changes = self.apply_changes(changes)?;
+        let rule = DeductionRule::Constraint(kind);
+        Ok(Some(Deduction::new(self.puzzle, rule, &changes)))
+    }
+
+    /// Makes deductions until no more can be made without guessing
+    pub fn propagate(&mut self) -> Result<(), Contradiction> {
+        while self.step()?.is_some() {}
+        Ok(())
+    }
+
+    /// Applies changes that are not already known and returns them
+    pub(crate) fn apply_changes(
+        &mut self,
+        changes: PuzzleMarkupChanges,
+    ) -> Result<PuzzleMarkupChanges, Contradiction> {
+        let mut changes = self.new_changes(changes)?;
+        if changes.cells.is_empty() && changes.cage_solution_removals.is_empty() {
+            return Ok(changes);
+        }
+        if !self.markup.sync_changes(&mut changes) {
+            return Err(Contradiction);
+        }
+        self.constraints
+            .notify_changes(&changes, self.markup.cells());
+        self.markup.apply_changes(&changes);
+        Ok(changes)
+    }
+
+    /// Removes changes that are already in the markup. Returns a `Contradiction` for changes
+    /// that conflict with solved cells, which the markup does not check.
+    fn new_changes(
+        &self,
+        changes: PuzzleMarkupChanges,
+    ) -> Result<PuzzleMarkupChanges, Contradiction> {
+        let mut new = PuzzleMarkupChanges {
+            cage_solution_removals: changes.cage_solution_removals,
+            reasons: changes.reasons,
+            ..PuzzleMarkupChanges::default()
+        };
+        let cells = self.markup.cells();
+        for (cell_id, change) in changes.cells {
+            let cell = &cells[cell_id];
+            match change {
+                CellChange::Solution(value) => {
+                    if let Some(solved) = cell.solved() {
+                        if solved == value {
+                            continue;
+                        }
+                        return Err(Contradiction);
+                    }
+                    if !cell.unsolved_and_contains(value) {
+                        return Err(Contradiction);
+                    }
+                    // solved cells in the same vector must have different values
+                    let duplicate = self.puzzle.cell(cell_id).vectors().iter().any(|&vector| {
+                        cells
+                            .vector(vector)
+                            .iter()
+                            .any(|other| other.solved() == Some(value))
+                    });
+                    if duplicate {
+                        return Err(Contradiction);
+                    }
+                    new.cells.solve(cell_id, value);
+                }
+                CellChange::DomainRemovals(values) => {
+                    if let Some(solved) = cell.solved() {
+                        if values.contains(&solved) {
+                            return Err(Contradiction);
+                        }
+                        continue;
+                    }
+                    for value in values {
+                        if cell.unsolved_and_contains(value) {
+                            new.cells.remove_domain_value(cell_id, value);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(new)
+    }
+
+    fn check_input(&self, cell_id: CellId, value: Value) -> Result<(), SolverStateError> {
+        if cell_id >= self.puzzle.cell_count() {
+            return Err(SolverStateError::InvalidInput {
+                msg: format!("the puzzle has no cell {}", cell_id),
+            });
+        }
+        let width = self.puzzle.width() as Value;
+        if !(1..=width).contains(&value) {
+            return Err(SolverStateError::InvalidInput {
+                msg: format!("{} is not a value from 1 to {}", value, width),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::SolverStateError;
+    use crate::solve::tests::{puzzle, solution};
+    use crate::solve::{DeductionRule, SolverState};
+
+    #[test]
+    fn candidates() {
+        let puzzle = puzzle();
+        let mut state = SolverState::new(&puzzle);
+        assert_eq!(vec![1, 2, 3, 4], state.candidates(0));
+        state.eliminate(0, 2).unwrap();
+        assert_eq!(vec![1, 3, 4], state.candidates(0));
+        state.set_value(0, 4).unwrap();
+        assert_eq!(vec![4], state.candidates(0));
+        assert_eq!(Some(4), state.value(0));
+        // the 4+ cage at R1C3
+        assert_eq!(vec![vec![1, 3], vec![3, 1]], state.cage_solutions(1));
+    }
+
+    #[test]
+    fn step() {
+        let puzzle = puzzle();
+        let mut state = SolverState::new(&puzzle);
+        let deduction = state.step().unwrap().unwrap();
+        assert_eq!(DeductionRule::CageArithmetic, deduction.rule);
+        assert_eq!(vec![0, 1, 4], deduction.cells);
+        assert_eq!(vec![1, 2, 4], state.candidates(0));
+        state.propagate().unwrap();
+        let solution = solution(&puzzle);
+        assert_eq!(Some(solution), state.solution());
+        assert!(state.step().unwrap().is_none());
+    }
+
+    #[test]
+    fn contradiction() {
+        let puzzle = puzzle();
+        let mut state = SolverState::new(&puzzle);
+        state.set_value(0, 1).unwrap();
+        assert!(matches!(
+            state.set_value(1, 1),
+            Err(SolverStateError::Contradiction(_))
+        ));
+        let mut state = SolverState::new(&puzzle);
+        // the single cell cage at R4C4 is 1
+        assert!(matches!(
+            state.set_value(15, 2),
+            Err(SolverStateError::Contradiction(_))
+        ));
+        let mut state = SolverState::new(&puzzle);
+        for value in 1..4 {
+            state.eliminate(0, value).unwrap();
+        }
+        assert!(matches!(
+            state.eliminate(0, 4),
+            Err(SolverStateError::Contradiction(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_input() {
+        let puzzle = puzzle();
+        let mut state = SolverState::new(&puzzle);
+        assert!(matches!(
+            state.set_value(0, 5),
+            Err(SolverStateError::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            state.eliminate(16, 1),
+            Err(SolverStateError::InvalidInput { .. })
+        ));
+        assert_eq!(vec![1, 2, 3, 4], state.candidates(0));
+    }
+}
@@ -1,21 +1,27 @@
-//! Save images of the puzzle in a series of solution steps
+//! Save images of the puzzle in a series of solution steps, and explain each step
 
 use std::fmt::Write;
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
 
-use crate::image::PuzzleImageBuilder;
+use crate::image::{GifWriter, ImageFormat, ImageTheme, PuzzleImageBuilder};
 use crate::puzzle::Puzzle;
-use crate::solve::markup::{CellChanges, PuzzleMarkup};
-
-static IMAGE_EXTENSION: &str = "svg";
+use crate::solve::explanation::ExplanationWriter;
+use crate::solve::markup::{PuzzleMarkup, PuzzleMarkupChanges};
+use crate::solve::SolveResult;
 
 // todo merge into PuzzleFolderBuilder?
 //   need to resolve visibility with solver code
 pub(crate) struct StepWriter<'a> {
     puzzle: &'a Puzzle,
-    path: PathBuf,
+    /// Where to save step images
+    images_path: Option<PathBuf>,
+    image_format: ImageFormat,
+    theme: &'a ImageTheme,
+    /// Adds every step image to an animation
+    animation: Option<GifWriter>,
+    explanation: Option<ExplanationWriter<'a>>,
     location: Vec<LocationNode>,
 }
 
@@ -25,32 +31,80 @@ struct LocationNode {
 }
 
 impl<'a> StepWriter<'a> {
-    pub fn new(puzzle: &'a Puzzle, path: PathBuf) -> Self {
+    pub fn new(
+        puzzle: &'a Puzzle,
+        images_path: Option<PathBuf>,
+        image_format: ImageFormat,
+        theme: &'a ImageTheme,
+        animation: Option<GifWriter>,
+        explain: bool,
+    ) -> Self {
         Self {
             puzzle,
-            path,
+            images_path,
+            image_format,
+            theme,
+            animation,
+            explanation: explain.then(|| ExplanationWriter::new(puzzle)),
             location: vec![LocationNode { branch: 0, step: 0 }],
         }
     }
 }
 
 impl StepWriter<'_> {
-    pub fn write_step(&mut self, markup: &PuzzleMarkup<'_>, changes: &CellChanges) -> Result<()> {
+    pub fn write_step(
+        &mut self,
+        markup: &PuzzleMarkup<'_>,
+        changes: &PuzzleMarkupChanges,
+    ) -> Result<()> {
+        if let Some(explanation) = &mut self.explanation {
+            explanation.write_step(changes, self.location.len() - 1);
+        }
+        if changes.cells.is_empty() {
+            return Ok(());
+        }
+        if self.images_path.is_none() && self.animation.is_none() {
+            return Ok(());
+        }
         self.location.last_mut().unwrap().step += 1;
-        let name = self.file_name();
-        let path = self.path.join(name);
-        debug!("writing step image: {}", path.display());
         let mut builder = PuzzleImageBuilder::new(self.puzzle);
         builder
-            .cell_variables(Some(&markup.cells()))
-            .cell_changes(changes);
+            .theme(self.theme)
+            .cell_variables(Some(markup.cells()))
+            .cell_changes(&changes.cells);
         let image = builder.build();
-        image
-            .save_svg(&path)
-            .with_context(|| format!("Error saving step image to {}", path.display()))?;
+        if let Some(images_path) = &self.images_path {
+            let path = images_path.join(self.file_name());
+            debug!("writing step image: {}", path.display());
+            image
+                .save(&path, self.image_format)
+                .with_context(|| format!("Error saving step image to {}", path.display()))?;
+        }
+        if let Some(animation) = &mut self.animation {
+            animation
+                .add_frame(&image)
+                .context("Error saving solve animation")?;
+        }
         Ok(())
     }
 
+    /// Writes a note in the explanation
+    pub fn write_note(&mut self, note: &str) {
+        if let Some(explanation) = &mut self.explanation {
+            explanation.write_note(note, self.location.len() - 1);
+        }
+    }
+
+    /// Finishes writing steps and the animation, and returns the explanation, if any
+    pub fn finish(self, result: &SolveResult) -> Result<Option<String>> {
+        if let Some(animation) = self.animation {
+            animation.finish().context("Error saving solve animation")?;
+        }
+        Ok(self
+            .explanation
+            .map(|explanation| explanation.finish(result)))
+    }
+
     pub fn start_search_branch(&mut self) {
         self.location.push(LocationNode { branch: 0, step: 0 });
     }
@@ -66,13 +120,14 @@ impl StepWriter<'_> {
         debug_assert!(!self.location.is_empty());
     }
 
-    fn file_name(&mut self) -> String {
-        let mut name = String::with_capacity(self.location.len() * 5 + 1 + IMAGE_EXTENSION.len());
+    fn file_name(&self) -> String {
+        let extension = self.image_format.extension();
+        let mut name = String::with_capacity(self.location.len() * 5 + 1 + extension.len());
         write!(name, "{:02}", self.location[0].step).unwrap();
         for &LocationNode { branch, step } in &self.location[1..] {
             write!(name, "_{}_{:02}", branch, step).unwrap();
         }
-        write!(name, ".{}", IMAGE_EXTENSION).unwrap();
+        write!(name, ".{}", extension).unwrap();
         name
     }
 }
@@ -0,0 +1,260 @@
+//! Draw puzzles as text with box-drawing characters
+
+use itertools::Itertools;
+
+use crate::collections::square::SquareValue;
+use crate::puzzle::{CellId, PlayerGrid, Puzzle, Solution, Value};
+use crate::solve::SolverState;
+
+/// The narrowest cell that is drawn, not counting borders
+const MIN_CELL_WIDTH: usize = 4;
+
+/// Creates a text drawing of a puzzle with optional values and cell domains
+pub struct PuzzleTextBuilder<'a> {
+    puzzle: &'a Puzzle,
+    values: Vec<Option<Value>>,
+    domains: Option<Vec<Vec<Value>>>,
+}
+
+impl<'a> PuzzleTextBuilder<'a> {
+    pub fn new(puzzle: &'a Puzzle) -> Self {
+        Self {
+            puzzle,
+            values: vec![None; puzzle.cell_count()],
+            domains: None,
+        }
+    }
+
+    pub fn solution(&mut self, solution: &Solution) -> &mut Self {
+        self.values = solution.iter().map(|&value| Some(value)).collect();
+        self
+    }
+
+    /// Shows the values entered in a player's grid
+    pub fn entries(&mut self, grid: &PlayerGrid) -> &mut Self {
+        self.values = grid.entries().to_vec();
+        self
+    }
+
+    /// Shows the solved values of a solver state and the domains of unsolved cells
+    pub fn state(&mut self, state: &SolverState<'_>) -> &mut Self {
+        let cells = 0..self.puzzle.cell_count();
+        self.values = cells.clone().map(|cell_id| state.value(cell_id)).collect();
+        self.domains = Some(
+            cells
+                .map(|cell_id| match state.value(cell_id) {
+                    Some(_) => Vec::new(),
+                    None => state.candidates(cell_id),
+                })
+                .collect(),
+        );
+        self
+    }
+
+    pub fn build(&self) -> String {
+        let labels = cage_labels(self.puzzle);
+        let domain_width = self.domains.as_ref().map_or(0, |domains| {
+            domains
+                .iter()
+                .map(|domain| domain_text(domain, self.puzzle.width()).len())
+                .max()
+                .unwrap_or(0)
+        });
+        let cell_width = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .chain([domain_width, MIN_CELL_WIDTH])
+            .max()
+            .unwrap();
+        let cell_height = if self.domains.is_some() { 3 } else { 2 };
+        let lines = draw_grid(self.puzzle, cell_width, cell_height, |cell_id, line| {
+            let text = match line {
+                0 => cage_label(self.puzzle, &labels, cell_id).to_string(),
+                1 => self.values[cell_id].map_or(String::new(), |value| value.to_string()),
+                _ => domain_text(
+                    &self.domains.as_ref().unwrap()[cell_id],
+                    self.puzzle.width(),
+                ),
+            };
+            match line {
+                0 => format!("{:<w$}", text, w = cell_width),
+                _ => format!("{:^w$}", text, w = cell_width),
+            }
+        });
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+}
+
+/// The target and operator of every cage, like "12×", in order of `CageId`
+pub fn cage_labels(puzzle: &Puzzle) -> Vec<String> {
+    puzzle
+        .cages()
+        .map(|cage| match cage.operator().display_symbol() {
+            Some(symbol) => format!("{}{}", cage.target(), symbol),
+            None => cage.target().to_string(),
+        })
+        .collect()
+}
+
+/// The label of a cell's cage if it is the first cell of the cage, and otherwise ""
+pub fn cage_label<'a>(puzzle: &Puzzle, labels: &'a [String], cell_id: CellId) -> &'a str {
+    let cage_id = puzzle.cell_cage_indices()[cell_id];
+    if puzzle.cage(cage_id).cell_ids()[0] == cell_id {
+        &labels[cage_id]
+    } else {
+        ""
+    }
+}
+
+/// Draws the grid of a puzzle with solid lines for cage borders and dotted lines between cells of
+/// the same cage. `cell_line` gives a line of text inside a cell from its `CellId` and the line
+/// number, from 0 to `cell_height - 1`. The text is expected to fill `cell_width` columns and may
+/// contain ANSI escape sequences.
+pub fn draw_grid(
+    puzzle: &Puzzle,
+    cell_width: usize,
+    cell_height: usize,
+    mut cell_line: impl FnMut(CellId, usize) -> String,
+) -> Vec<String> {
+    let width = puzzle.width() as usize;
+    let borders = Borders { puzzle, width };
+    let mut lines = Vec::new();
+    for row in 0..=width {
+        let mut line = String::new();
+        for col in 0..=width {
+            line.push(borders.junction(row, col));
+            if col < width {
+                let c = if borders.horizontal(row, col) {
+                    '─'
+                } else {
+                    '┄'
+                };
+                line.extend(std::iter::repeat_n(c, cell_width));
+            }
+        }
+        lines.push(line);
+        if row == width {
+            break;
+        }
+        for i in 0..cell_height {
+            let mut line = String::new();
+            for col in 0..=width {
+                line.push(if borders.vertical(row, col) {
+                    '│'
+                } else {
+                    '┆'
+                });
+                if col < width {
+                    line.push_str(&cell_line(row * width + col, i));
+                }
+            }
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// The candidate values of a cell, like "124". Values are separated by spaces when the puzzle
+/// is wider than 9, since they may have more than one digit.
+fn domain_text(domain: &[Value], width: SquareValue) -> String {
+    let separator = if width > 9 { " " } else { "" };
+    domain.iter().map(ToString::to_string).join(separator)
+}
+
+/// Finds where cage borders are drawn. Rows and columns of borders are numbered from 0 to the
+/// puzzle width, and the outer border is always drawn.
+struct Borders<'a> {
+    puzzle: &'a Puzzle,
+    width: usize,
+}
+
+impl Borders<'_> {
+    /// True if there is a cage border above the cell at (row, col)
+    fn horizontal(&self, row: usize, col: usize) -> bool {
+        row == 0 || row == self.width || self.cage(row - 1, col) != self.cage(row, col)
+    }
+
+    /// True if there is a cage border left of the cell at (row, col)
+    fn vertical(&self, row: usize, col: usize) -> bool {
+        col == 0 || col == self.width || self.cage(row, col - 1) != self.cage(row, col)
+    }
+
+    /// The character where borders meet at the top-left corner of the cell at (row, col)
+    fn junction(&self, row: usize, col: usize) -> char {
+        let up = row > 0 && self.vertical(row - 1, col);
+        let down = row < self.width && self.vertical(row, col);
+        let left = col > 0 && self.horizontal(row, col - 1);
+        let right = col < self.width && self.horizontal(row, col);
+        match (up, down, left, right) {
+            (false, false, false, false) => '·',
+            (true, false, false, false) => '╵',
+            (false, true, false, false) => '╷',
+            (false, false, true, false) => '╴',
+            (false, false, false, true) => '╶',
+            (true, true, false, false) => '│',
+            (false, false, true, true) => '─',
+            (false, true, false, true) => '┌',
+            (false, true, true, false) => '┐',
+            (true, false, false, true) => '└',
+            (true, false, true, false) => '┘',
+            (true, true, false, true) => '├',
+            (true, true, true, false) => '┤',
+            (false, true, true, true) => '┬',
+            (true, false, true, true) => '┴',
+            (true, true, true, true) => '┼',
+        }
+    }
+
+    fn cage(&self, row: usize, col: usize) -> usize {
+        self.puzzle.cell_cage_indices()[row * self.width + col]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solve::tests::puzzle;
+    use crate::solve::SolverState;
+    use crate::text::{domain_text, PuzzleTextBuilder};
+
+    #[test]
+    fn puzzle_text() {
+        let puzzle = puzzle();
+        let expected = "\
+┌─────────┬─────────┐
+│8×  ┆    │4+  ┆    │
+│    ┆    │    ┆    │
+│┄┄┄┄┌────┴─────────┤
+│    │9+  ┆    ┆    │
+│    │    ┆    ┆    │
+├────┴────┬────┬────┤
+│8+  ┆    │2   │4   │
+│    ┆    │    │    │
+│┄┄┄┄┌────┴────┼────┤
+│    │5+  ┆    │1   │
+│    │    ┆    │    │
+└────┴─────────┴────┘
+";
+        assert_eq!(expected, PuzzleTextBuilder::new(&puzzle).build());
+    }
+
+    #[test]
+    fn state_text() {
+        let puzzle = puzzle();
+        let mut state = SolverState::new(&puzzle);
+        state.set_value(0, 2).unwrap();
+        state.step().unwrap();
+        let text = PuzzleTextBuilder::new(&puzzle).state(&state).build();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!("│8×  ┆    │4+  ┆    │", lines[1]);
+        assert_eq!("│ 2  ┆    │    ┆    │", lines[2]);
+        assert_eq!("│    ┆124 │1234┆1234│", lines[3]);
+    }
+
+    #[test]
+    fn wide_domain_text() {
+        assert_eq!("124", domain_text(&[1, 2, 4], 4));
+        assert_eq!("1 10 12", domain_text(&[1, 10, 12], 12));
+    }
+}
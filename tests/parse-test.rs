@@ -0,0 +1,31 @@
+use proptest::prelude::*;
+
+use kenny::generate::{GeneratorConfigBuilder, PuzzleGenerator};
+use kenny::puzzle::Puzzle;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    /// Every generated puzzle is written and parsed back without changes, including puzzles with
+    /// more cages than letters and puzzles with metadata
+    #[test]
+    fn display_parse_round_trip(
+        seed: u64,
+        width in 3..=9u32,
+        average_cage_size in 1.0..3.0f32,
+        hide_operators: bool,
+    ) {
+        let config = GeneratorConfigBuilder::new()
+            .average_cage_size(average_cage_size)
+            .hide_operators(hide_operators)
+            .seed(seed)
+            .build()
+            .unwrap();
+        let mut puzzle = PuzzleGenerator::new(config).generate_untested(width);
+        puzzle.metadata_mut().insert("seed", &seed.to_string());
+        let str = puzzle.to_string();
+        let parsed = Puzzle::parse(&str).unwrap();
+        prop_assert_eq!(&puzzle, &parsed);
+        prop_assert_eq!(str, parsed.to_string());
+    }
+}
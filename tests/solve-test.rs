@@ -3,13 +3,14 @@ use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 
-use kenny::puzzle::Puzzle;
-use kenny::solve::PuzzleSolver;
+use kenny::puzzle::{PlayerGrid, Puzzle};
+use kenny::solve::{DifficultyTier, PuzzleSolver, SolveResult};
 
 #[test]
 fn test_puzzles() -> Result<()> {
     test_puzzle_dir(project_path("res/test/puzzles/require-search"), true)?;
     test_puzzle_dir(project_path("res/test/puzzles/no-require-search"), false)?;
+    test_puzzle_dir(project_path("res/test/puzzles/no-op"), false)?;
     Ok(())
 }
 
@@ -32,10 +33,107 @@ fn test_puzzle_dir(path: impl AsRef<Path>, require_search: bool) -> Result<()> {
             "{}",
             file.path().display()
         );
+        assert_eq!(
+            data.difficulty.tier() == DifficultyTier::Expert,
+            require_search,
+            "{}",
+            file.path().display()
+        );
+        assert_eq!(
+            1,
+            PuzzleSolver::new(&puzzle).count_solutions(None),
+            "{}",
+            file.path().display()
+        );
     }
     Ok(())
 }
 
+#[test]
+fn count_solutions() {
+    // every Latin square of width 3 is a solution
+    let puzzle = Puzzle::parse("3\nAAA\nBBB\nCCC\n6+ 6+ 6+").unwrap();
+    let solver = PuzzleSolver::new(&puzzle);
+    let solutions: Vec<_> = solver.solutions().collect();
+    assert_eq!(12, solutions.len());
+    assert!(solutions
+        .iter()
+        .all(|solution| puzzle.verify_solution(solution)));
+    for (i, solution) in solutions.iter().enumerate() {
+        assert!(!solutions[i + 1..].contains(solution));
+    }
+    assert_eq!(12, solver.count_solutions(None));
+    assert_eq!(5, solver.count_solutions(Some(5)));
+}
+
 fn project_path(path: impl AsRef<Path>) -> PathBuf {
     Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
 }
+
+#[test]
+fn multiple_solutions() {
+    let puzzle = Puzzle::parse("3\nAAA\nBBB\nCCC\n6+ 6+ 6+").unwrap();
+    let data = match PuzzleSolver::new(&puzzle).solve().unwrap() {
+        SolveResult::MultipleSolutions(data) => data,
+        _ => panic!("expected multiple solutions"),
+    };
+    let [a, b] = &data.solutions;
+    assert!(puzzle.verify_solution(a));
+    assert!(puzzle.verify_solution(b));
+    assert!(!data.differing_cells.is_empty());
+    for i in 0..9 {
+        assert_eq!(a[i] != b[i], data.differing_cells.contains(&i));
+    }
+}
+
+#[test]
+fn explanation() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("explanation.txt");
+    let puzzle = Puzzle::from_file(&project_path("res/test/puzzles/require-search/w4_001"))?;
+    let result = PuzzleSolver::new(&puzzle).save_explanation(&path).solve()?;
+    assert!(result.is_solved());
+    let explanation = fs::read_to_string(&path)?;
+    assert!(explanation.starts_with("1. "));
+    assert!(explanation.contains("Guess that "));
+    assert!(explanation.ends_with("The puzzle is solved.\n"));
+    // steps are numbered in order
+    let numbers: Vec<u32> = explanation
+        .lines()
+        .filter_map(|line| line.trim_start().split_once(". "))
+        .filter_map(|(number, _)| number.parse().ok())
+        .collect();
+    assert!(!numbers.is_empty());
+    assert!(numbers.iter().copied().eq(1..=numbers.len() as u32));
+    Ok(())
+}
+
+#[test]
+fn hints_solve_puzzles() -> Result<()> {
+    let mut files: Vec<_> = fs::read_dir(project_path("res/test/puzzles/no-require-search"))?
+        .map(|f| f.unwrap().path())
+        .collect();
+    files.sort_unstable();
+    // a sample of puzzles since each hint starts from the beginning
+    for path in files.into_iter().step_by(10) {
+        let puzzle = Puzzle::from_file(&path)?;
+        let solver = PuzzleSolver::new(&puzzle);
+        let width = puzzle.width() as i32;
+        let mut grid = PlayerGrid::new(puzzle.width());
+        let mut candidates = vec![(1..=width).collect::<Vec<_>>(); puzzle.cell_count()];
+        while let Some(hint) = solver.hint(&grid)? {
+            for &(cell, value) in &hint.solved {
+                grid.set_entry(cell, Some(value));
+            }
+            for (cell, values) in &hint.eliminated {
+                candidates[*cell].retain(|value| !values.contains(value));
+                grid.set_pencil_marks(*cell, &candidates[*cell]);
+            }
+        }
+        let solution = solver.solve()?.solved().unwrap().solution.clone();
+        for (cell, &value) in solution.iter().enumerate() {
+            assert_eq!(Some(value), grid.entry(cell), "{}", path.display());
+        }
+    }
+    Ok(())
+}